@@ -0,0 +1,8 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "grpc")]
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/taskinator.proto"], &["proto"])?;
+
+    Ok(())
+}
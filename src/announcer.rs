@@ -0,0 +1,172 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Mutex,
+};
+use twilight_model::{
+    channel::embed::Embed,
+    id::{ChannelId, MessageId},
+};
+
+use crate::{
+    http::{is_not_found, Http},
+    Result,
+};
+
+pub enum Body {
+    Text(String),
+    Embed(Embed),
+    File(&'static str, Vec<u8>),
+}
+
+pub enum Delivery {
+    Post,
+    // Only the latest of those queued with the same key is delivered, and it edits the last one
+    // sent if the announcer hasn't posted anything since, e.g. an updated list of AFK players
+    Replace(&'static str),
+}
+
+pub struct Announcement {
+    body: Body,
+    delivery: Delivery,
+}
+
+impl Announcement {
+    pub fn text(content: impl Into<String>) -> Self {
+        Self {
+            body: Body::Text(content.into()),
+            delivery: Delivery::Post,
+        }
+    }
+
+    pub fn embed(embed: Embed) -> Self {
+        Self {
+            body: Body::Embed(embed),
+            delivery: Delivery::Post,
+        }
+    }
+
+    pub fn file(name: &'static str, contents: Vec<u8>) -> Self {
+        Self {
+            body: Body::File(name, contents),
+            delivery: Delivery::Post,
+        }
+    }
+
+    pub fn replacing(self, key: &'static str) -> Self {
+        Self {
+            delivery: Delivery::Replace(key),
+            ..self
+        }
+    }
+
+    fn key(&self) -> Option<&'static str> {
+        match self.delivery {
+            Delivery::Post => None,
+            Delivery::Replace(key) => Some(key),
+        }
+    }
+}
+
+// Broadcast channel messages are queued for their own task, so a slow or rate limited channel
+// never holds up muting
+#[derive(Clone)]
+pub struct Announcer {
+    tx: UnboundedSender<Announcement>,
+    // Kept here so the task can pick up where it left off if it's restarted
+    rx: Arc<Mutex<UnboundedReceiver<Announcement>>>,
+}
+
+impl Default for Announcer {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        Self {
+            tx,
+            rx: Arc::new(Mutex::new(rx)),
+        }
+    }
+}
+
+impl Announcer {
+    pub fn send(&self, announcement: Announcement) {
+        if self.tx.send(announcement).is_err() {
+            tracing::warn!("The announcer has stopped, dropping an announcement");
+        }
+    }
+
+    pub async fn run(self, client: Http, channel_id: ChannelId) -> Result<()> {
+        let mut rx = self.rx.lock().await;
+        let mut sent = HashMap::new();
+        let mut last_posted = None;
+
+        while let Some(first) = rx.recv().await {
+            let mut queued = vec![first];
+            while let Ok(announcement) = rx.try_recv() {
+                queued.push(announcement);
+            }
+
+            for (index, announcement) in queued.iter().enumerate() {
+                let superseded = announcement.key().map_or(false, |key| {
+                    queued[index + 1..]
+                        .iter()
+                        .any(|later| later.key() == Some(key))
+                });
+                if superseded {
+                    continue;
+                }
+
+                let edit = announcement
+                    .key()
+                    .and_then(|key| sent.get(key).copied())
+                    .filter(|&id| Some(id) == last_posted);
+                // One failure, e.g. a missing permission, shouldn't cost the rest of the queue
+                match deliver(&client, channel_id, &announcement.body, edit).await {
+                    Ok(id) => {
+                        if let Some(key) = announcement.key() {
+                            sent.insert(key, id);
+                        }
+                        last_posted = Some(id);
+                    }
+                    Err(why) => tracing::warn!("Failed to post an announcement: {}", why),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn deliver(
+    client: &Http,
+    channel_id: ChannelId,
+    body: &Body,
+    edit: Option<MessageId>,
+) -> Result<MessageId> {
+    if let Some(id) = edit {
+        let update = client.update_message(channel_id, id);
+        let update = match body {
+            Body::Text(content) => Some(update.content(Some(content.clone()))?),
+            Body::Embed(embed) => Some(update.embed(Some(embed.clone()))?),
+            // Attachments can't be swapped out, so they're always posted afresh
+            Body::File(..) => None,
+        };
+        if let Some(update) = update {
+            match update.await {
+                Ok(_) => return Ok(id),
+                Err(why) if is_not_found(&why) => {}
+                Err(why) => return Err(why.into()),
+            }
+        }
+    }
+
+    let create = client.create_message(channel_id);
+    let message = match body {
+        Body::Text(content) => create.content(content.clone())?.await?,
+        Body::Embed(embed) => create.embed(embed.clone())?.await?,
+        Body::File(name, contents) => create.attachment(*name, contents.clone()).await?,
+    };
+
+    Ok(message.id)
+}
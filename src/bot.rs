@@ -1,55 +1,226 @@
 use std::{
-    collections::{HashMap, HashSet},
-    fmt::Debug,
-    future::Future,
-    path::Path,
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryFrom,
+    path::PathBuf,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant, UNIX_EPOCH},
 };
 
 use futures::StreamExt;
-use parking_lot::RwLock;
-use taskinator_communicator::game::{MeetingState, Player, State};
-use tokio::{signal::ctrl_c, sync::watch::Receiver, time::sleep};
+use parking_lot::{Mutex, RwLock};
+use taskinator_communicator::game::{Player, State};
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc::UnboundedReceiver, watch::Receiver},
+    time::{interval, sleep},
+};
+use tracing::{Instrument, Level};
 use twilight_cache_inmemory::{model::CachedMember, InMemoryCache, ResourceType};
 use twilight_command_parser::{Arguments, Command, CommandParserConfig, Parser};
-use twilight_embed_builder::{EmbedBuilder, EmbedFieldBuilder};
+use twilight_embed_builder::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder, ImageSource};
 use twilight_gateway::{shard::Events, Event, EventTypeFlags, Intents, Shard};
-use twilight_http::{error::Error as HttpError, Client};
+use twilight_http::error::Error as HttpError;
 use twilight_mention::{Mention, ParseMention};
 use twilight_model::{
-    channel::{Channel, GuildChannel, Message},
-    id::{ChannelId, UserId},
+    application::{
+        callback::{CallbackData, InteractionResponse},
+        interaction::{Interaction, MessageComponentInteraction},
+    },
+    channel::{embed::Embed, message::MessageFlags, Channel, GuildChannel, Message},
+    gateway::payload::MemberAdd,
+    id::{ChannelId, GuildId, MessageId, RoleId, UserId},
+    user::User,
 };
 
 use crate::{
-    config::Config,
-    utils::{KnownAs, ReplyTo},
-    Result,
+    announcer::{Announcement, Announcer},
+    config::{CommandFeedback, Config, ControlPermission, LateJoinerPolicy, MutePacing, Profile},
+    confirm::{self, Action, Confirmations, Pending},
+    diag::{self, Sanitizer, StateHistory},
+    diff::{voting, Change, Differ},
+    emoji::{Emoji, EmojiSet, Status},
+    history,
+    http::{is_forbidden, is_not_found, Http, MemberUpdate},
+    lease::Lease,
+    logs::LogBuffer,
+    matching::{self, Matcher, Strategy},
+    panel::{self, PanelAction},
+    plan::MutePlan,
+    reporting,
+    rules::{HouseRule, Trigger},
+    session::{Phase, SessionEvent, SessionStore},
+    signals,
+    source::Snapshot,
+    store::Store,
+    summary::{SessionSummary, Team},
+    supervisor::{Policy, Report, Supervisor},
+    teams,
+    telemetry::Telemetry,
+    transcript::Transcript,
+    utils::{code_index, parse_minutes, short_code, visible_name, KnownAs, Recent, ReplyTo},
+    welcome, Result, CONFIG_PATH,
 };
 
 type TwiResult<T> = std::result::Result<T, HttpError>;
 
-enum BotState {
-    PreGame,
-    InGame,
-    InMeeting,
-    GameOver,
+const DAY_SECS: u64 = 24 * 60 * 60;
+const HANDLED_MESSAGES: usize = 1000;
+
+const COMMANDS: &[&str] = &[
+    "ident",
+    "forcematch",
+    "swap",
+    "check",
+    "status",
+    "stop",
+    "pause",
+    "endgame",
+    "command",
+    "profile",
+    "setup",
+    "session",
+    "plan",
+    "gather",
+    "optout",
+    "buddy",
+    "wrapup",
+    "diag",
+    "meeting",
+    "celebrate",
+    "arm",
+    "disarm",
+    "normalize",
+    "game",
+    "queue",
+    "tasks",
+    "panic",
+    "history",
+    "teams",
+    "back",
+    "emoji",
+    "logs",
+    "controls",
+    "claim",
+    "takeover",
+];
+
+// The settings that can be overridden by a profile
+#[derive(Clone)]
+struct Settings {
+    living_channel: ChannelId,
+    split_channels: Vec<ChannelId>,
+    // Members here take part in the game where they are, they're never moved for meetings
+    participant_channels: Vec<ChannelId>,
+    dead_channel: ChannelId,
+    // Spectators talk freely here, and long dead players can be moved here out of the dead channel
+    lounge_channel: Option<ChannelId>,
+    reconnect_grace: Duration,
+    meeting_end_delay: Duration,
+    late_joiner_policy: LateJoinerPolicy,
+}
+
+impl Settings {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            living_channel: config.living_channel,
+            split_channels: config.split_channels.clone(),
+            participant_channels: config.participant_channels.clone(),
+            dead_channel: config.dead_channel,
+            lounge_channel: config.lounge_channel,
+            reconnect_grace: Duration::from_secs(config.reconnect_grace_secs),
+            meeting_end_delay: Duration::from_secs(config.meeting_end_delay_secs),
+            late_joiner_policy: config.late_joiner_policy,
+        }
+    }
+
+    // Every voice channel the settings refer to
+    fn channels(&self) -> Vec<ChannelId> {
+        let mut channels = vec![self.living_channel, self.dead_channel];
+        channels.extend(&self.split_channels);
+        channels.extend(&self.participant_channels);
+        channels.extend(&self.lounge_channel);
+        channels
+    }
+
+    fn with_profile(&self, profile: &Profile) -> Self {
+        Self {
+            living_channel: profile.living_channel.unwrap_or(self.living_channel),
+            split_channels: profile
+                .split_channels
+                .clone()
+                .unwrap_or_else(|| self.split_channels.clone()),
+            participant_channels: profile
+                .participant_channels
+                .clone()
+                .unwrap_or_else(|| self.participant_channels.clone()),
+            dead_channel: profile.dead_channel.unwrap_or(self.dead_channel),
+            lounge_channel: profile.lounge_channel.or(self.lounge_channel),
+            reconnect_grace: profile
+                .reconnect_grace_secs
+                .map_or(self.reconnect_grace, Duration::from_secs),
+            meeting_end_delay: profile
+                .meeting_end_delay_secs
+                .map_or(self.meeting_end_delay, Duration::from_secs),
+            late_joiner_policy: profile
+                .late_joiner_policy
+                .unwrap_or(self.late_joiner_policy),
+        }
+    }
 }
 
 pub struct Builder {
     cache: InMemoryCache,
     discord_gateway: Shard,
     gateway_events: Events,
-    discord_client: Client,
+    discord_client: Http,
     command_parser: Arc<Parser<'static>>,
-    broadcast_channel: ChannelId,
-    living_channel: ChannelId,
-    dead_channel: ChannelId,
+    broadcast_channel: Option<ChannelId>,
+    settings: Settings,
+    profiles: BTreeMap<String, Profile>,
+    announce_unmatched: bool,
+    announce_impostors: bool,
+    reveal_roles: bool,
+    hush_voting: bool,
+    nickname_status: bool,
+    status_board: bool,
+    lounge_after_meetings: Option<u32>,
+    afk_after_meetings: Option<u32>,
+    ident_ttl: Duration,
+    max_state_age: Duration,
+    kick_channel: Option<ChannelId>,
+    afk_channel: Option<ChannelId>,
+    afk_channel_after: Duration,
+    cue_channel: Option<ChannelId>,
+    kick_mute: bool,
+    require_arming: bool,
+    mute_in_place: bool,
+    mute_pacing: MutePacing,
+    mute_pacing_interval: Duration,
+    failure_budget: u32,
+    lobby_size: usize,
+    archive_channel: Option<ChannelId>,
+    quiet_channels: Vec<ChannelId>,
+    muted_bots: Vec<UserId>,
+    emoji: EmojiSet,
+    matching: Vec<Strategy>,
+    rules: Vec<HouseRule>,
+    quiet_slow_mode: u64,
+    session_idle: Duration,
+    lobby_cleanup: Duration,
+    transcript_path: Option<PathBuf>,
+    transcript_upload: bool,
+    control_permission: ControlPermission,
+    ignored_command_feedback: CommandFeedback,
+    store_path: PathBuf,
+    lock_path: PathBuf,
 }
 
 impl Builder {
-    pub async fn build(self, game_state_rx: Receiver<Option<State>>) -> Result<(Bot, Events)> {
+    pub async fn build(
+        self,
+        game_state_rx: Receiver<Option<Snapshot>>,
+        supervisor: Supervisor,
+        logs: LogBuffer,
+    ) -> Result<(Bot, Events)> {
         let (owners, bot_id) = {
             let mut owners = HashSet::new();
 
@@ -62,27 +233,40 @@ impl Builder {
             (Arc::new(owners), UserId(app_info.id.0))
         };
 
+        let store = match Store::open(&self.store_path) {
+            Ok(store) => Arc::new(store),
+            Err(why) => {
+                tracing::error!("Failed to read the store file. Aborting!");
+                return Err(why);
+            }
+        };
+
         // Validate channels
-        let broadcast_channel = if let Channel::Guild(channel) = self
-            .discord_client
-            .channel(self.broadcast_channel)
-            .await?
-            .expect("Failed to retreive the broadcast channel")
-        {
-            if let GuildChannel::Text(tc) = channel {
-                tc
-            } else {
-                tracing::error!("Broadcast channel must be a text channel");
-                panic!();
+        let broadcast_channel = match self.broadcast_channel {
+            Some(broadcast_channel) => {
+                if let Channel::Guild(channel) = self
+                    .discord_client
+                    .channel(broadcast_channel)
+                    .await?
+                    .expect("Failed to retreive the broadcast channel")
+                {
+                    if let GuildChannel::Text(tc) = channel {
+                        Some(tc.id)
+                    } else {
+                        tracing::error!("Broadcast channel must be a text channel");
+                        panic!();
+                    }
+                } else {
+                    tracing::error!("Broadcast channel must be in a guild.");
+                    panic!();
+                }
             }
-        } else {
-            tracing::error!("Broadcast channel must be in a guild.");
-            panic!();
+            None => None,
         };
 
         let living_channel = if let Channel::Guild(channel) = self
             .discord_client
-            .channel(self.living_channel)
+            .channel(self.settings.living_channel)
             .await?
             .expect("Failed to retreive the living channel")
         {
@@ -97,9 +281,27 @@ impl Builder {
             panic!();
         };
 
+        for &channel in self
+            .settings
+            .split_channels
+            .iter()
+            .chain(&self.settings.participant_channels)
+            .chain(&self.settings.lounge_channel)
+        {
+            match self.discord_client.channel(channel).await? {
+                Some(Channel::Guild(GuildChannel::Voice(_))) => {}
+                _ => {
+                    tracing::error!(
+                        "Split, participant and lounge channels must be voice channels in a guild"
+                    );
+                    panic!();
+                }
+            }
+        }
+
         let dead_channel = if let Channel::Guild(channel) = self
             .discord_client
-            .channel(self.dead_channel)
+            .channel(self.settings.dead_channel)
             .await?
             .expect("Failed to retreive the dead channel")
         {
@@ -114,6 +316,40 @@ impl Builder {
             panic!();
         };
 
+        // Pick up the profile the living channel's guild was last using
+        let mut settings = self.settings.clone();
+        let stored = living_channel
+            .guild_id
+            .and_then(|guild_id| Some((guild_id, store.guild(guild_id).profile?)));
+        if let Some((guild_id, name)) = stored {
+            match self.profiles.get(&name) {
+                Some(profile) => {
+                    // Its channels were checked when it was switched to, but may have been
+                    // deleted or moved to another server since
+                    let profiled = self.settings.with_profile(profile);
+                    let mut usable = true;
+                    for channel in profiled.channels() {
+                        if !is_voice_channel_in(&self.discord_client, guild_id, channel).await? {
+                            tracing::warn!(
+                                "The stored profile {} uses {}, which is not a voice channel in \
+                                the server, using the default settings",
+                                name,
+                                channel
+                            );
+                            usable = false;
+                            break;
+                        }
+                    }
+
+                    if usable {
+                        tracing::info!("Using profile {}", name);
+                        settings = profiled;
+                    }
+                }
+                None => tracing::warn!("The stored profile {} is no longer configured", name),
+            }
+        }
+
         Ok((
             Bot {
                 cache: self.cache,
@@ -122,72 +358,177 @@ impl Builder {
                 command_parser: self.command_parser,
                 bot_id,
                 owners,
-                broadcast_channel: broadcast_channel.id,
-                living_channel: living_channel.id,
-                dead_channel: dead_channel.id,
-                player_names: Arc::new(RwLock::new(HashMap::new())),
+                home_guild: living_channel.guild_id,
+                broadcast_channel,
+                defaults: Arc::new(RwLock::new(Settings {
+                    living_channel: living_channel.id,
+                    dead_channel: dead_channel.id,
+                    ..self.settings
+                })),
+                settings: Arc::new(RwLock::new(settings)),
+                profiles: Arc::new(RwLock::new(self.profiles)),
+                announce_unmatched: self.announce_unmatched,
+                announce_impostors: self.announce_impostors,
+                reveal_roles: self.reveal_roles,
+                hush_voting: self.hush_voting,
+                nickname_status: self.nickname_status,
+                status_board: self.status_board,
+                lounge_after_meetings: self.lounge_after_meetings,
+                afk_after_meetings: self.afk_after_meetings,
+                ident_ttl: self.ident_ttl,
+                max_state_age: self.max_state_age,
+                kick_channel: self.kick_channel,
+                afk_channel: self.afk_channel,
+                afk_channel_after: self.afk_channel_after,
+                cue_channel: self.cue_channel,
+                kick_mute: self.kick_mute,
+                require_arming: self.require_arming,
+                mute_in_place: self.mute_in_place,
+                mute_pacing: self.mute_pacing,
+                mute_pacing_interval: self.mute_pacing_interval,
+                failure_budget: self.failure_budget,
+                lobby_size: self.lobby_size,
+                archive_channel: self.archive_channel,
+                quiet_channels: Arc::new(self.quiet_channels),
+                muted_bots: Arc::new(self.muted_bots),
+                emoji: Arc::new(self.emoji),
+                matching: Arc::new(self.matching),
+                rules: Arc::new(self.rules),
+                quiet_slow_mode: self.quiet_slow_mode,
+                session_idle: self.session_idle,
+                lobby_cleanup: self.lobby_cleanup,
+                transcript_path: self.transcript_path,
+                transcript_upload: self.transcript_upload,
+                control_permission: self.control_permission,
+                ignored_command_feedback: self.ignored_command_feedback,
+                store,
+                lease: Arc::new(Lease::new(self.lock_path)),
+                locked_out: Arc::new(Mutex::new(None)),
+                session: Arc::new(SessionStore::new()),
+                transcript: Arc::new(Mutex::new(None)),
+                summary: Arc::new(Mutex::new(None)),
+                telemetry: Arc::new(Telemetry::default()),
+                confirmations: Arc::new(Confirmations::default()),
+                handled_messages: Arc::new(Mutex::new(Recent::new(HANDLED_MESSAGES))),
                 game_state_rx,
+                supervisor,
+                logs,
+                states: Arc::new(Mutex::new(StateHistory::default())),
+                announcer: Announcer::default(),
             },
             self.gateway_events,
         ))
     }
 }
 
+enum Ignored {
+    Unknown,
+    Denied(&'static str),
+}
+
+pub struct RosterEntry {
+    pub name: String,
+    pub dead: bool,
+    pub user_id: Option<UserId>,
+}
+
 #[derive(Clone)]
 pub struct Bot {
     cache: InMemoryCache,
     discord_gateway: Shard,
-    discord_client: Client,
+    discord_client: Http,
     command_parser: Arc<Parser<'static>>,
     bot_id: UserId,
     owners: Arc<HashSet<UserId>>,
-    broadcast_channel: ChannelId,
-    living_channel: ChannelId,
-    dead_channel: ChannelId,
-    player_names: Arc<RwLock<HashMap<UserId, String>>>,
-    game_state_rx: Receiver<Option<State>>,
+    home_guild: Option<GuildId>,
+    broadcast_channel: Option<ChannelId>,
+    defaults: Arc<RwLock<Settings>>,
+    settings: Arc<RwLock<Settings>>,
+    profiles: Arc<RwLock<BTreeMap<String, Profile>>>,
+    announce_unmatched: bool,
+    announce_impostors: bool,
+    reveal_roles: bool,
+    hush_voting: bool,
+    nickname_status: bool,
+    status_board: bool,
+    lounge_after_meetings: Option<u32>,
+    afk_after_meetings: Option<u32>,
+    ident_ttl: Duration,
+    max_state_age: Duration,
+    kick_channel: Option<ChannelId>,
+    afk_channel: Option<ChannelId>,
+    afk_channel_after: Duration,
+    cue_channel: Option<ChannelId>,
+    kick_mute: bool,
+    require_arming: bool,
+    mute_in_place: bool,
+    mute_pacing: MutePacing,
+    mute_pacing_interval: Duration,
+    failure_budget: u32,
+    lobby_size: usize,
+    archive_channel: Option<ChannelId>,
+    // Text channels put in slow mode while a round is being played
+    quiet_channels: Arc<Vec<ChannelId>>,
+    muted_bots: Arc<Vec<UserId>>,
+    emoji: Arc<EmojiSet>,
+    matching: Arc<Vec<Strategy>>,
+    rules: Arc<Vec<HouseRule>>,
+    quiet_slow_mode: u64,
+    session_idle: Duration,
+    lobby_cleanup: Duration,
+    transcript_path: Option<PathBuf>,
+    transcript_upload: bool,
+    control_permission: ControlPermission,
+    ignored_command_feedback: CommandFeedback,
+    store: Arc<Store>,
+    lease: Arc<Lease>,
+    // The other instance holding the home server's lease, automation stays off while it does
+    locked_out: Arc<Mutex<Option<String>>>,
+    session: Arc<SessionStore>,
+    transcript: Arc<Mutex<Option<Transcript>>>,
+    summary: Arc<Mutex<Option<SessionSummary>>>,
+    telemetry: Arc<Telemetry>,
+    confirmations: Arc<Confirmations>,
+    // Discord can redeliver messages after a gateway resume, each command is only run once
+    handled_messages: Arc<Mutex<Recent<MessageId>>>,
+    game_state_rx: Receiver<Option<Snapshot>>,
+    supervisor: Supervisor,
+    logs: LogBuffer,
+    states: Arc<Mutex<StateHistory>>,
+    announcer: Announcer,
 }
 
 impl Bot {
-    pub fn builder(config_path: impl AsRef<Path>) -> Builder {
-        let config = match Config::from_file(config_path) {
-            Ok(config) => config,
-            Err(why) => {
-                tracing::error!("Failed to read the config file. Aborting!");
-                tracing::error!("{}", why);
-                panic!();
-            }
-        };
+    pub fn builder(config: &Config) -> Builder {
+        let discord_client = Http::new(&config.token);
 
-        let discord_client = Client::new(&config.token);
+        let intents = config.intents();
+        if !intents.contains(Intents::GUILD_VOICE_STATES) {
+            tracing::warn!(
+                "Without the guild-voice-states intent the bot can't see who is in voice"
+            );
+        }
+        if !intents.contains(Intents::GUILD_MESSAGES) {
+            tracing::info!("The guild-messages intent is disabled, text commands will be ignored");
+        }
 
         let event_flags: EventTypeFlags = EventTypeFlags::GUILD_CREATE
+            | EventTypeFlags::INTERACTION_CREATE
             | EventTypeFlags::MEMBER_ADD
             | EventTypeFlags::MEMBER_UPDATE
             | EventTypeFlags::MESSAGE_CREATE
             | EventTypeFlags::VOICE_STATE_UPDATE;
 
-        let (discord_gateway, gateway_events) = Shard::builder(
-            &config.token,
-            Intents::GUILDS
-                | Intents::GUILD_MEMBERS
-                | Intents::GUILD_MESSAGES
-                | Intents::GUILD_VOICE_STATES,
-        )
-        .event_types(event_flags)
-        .build();
-
-        let (broadcast_channel, living_channel, dead_channel) = (
-            config.broadcast_channel,
-            config.living_channel,
-            config.dead_channel,
-        );
+        let (discord_gateway, gateway_events) = Shard::builder(&config.token, intents)
+            .event_types(event_flags)
+            .build();
 
         let cache = InMemoryCache::builder()
             .resource_types(
-                ResourceType::CHANNEL
-                    | ResourceType::GUILD
+                // Matching players and checking the role hierarchy need these whatever the config says
+                config.cache_resources()
                     | ResourceType::MEMBER
+                    | ResourceType::ROLE
                     | ResourceType::USER
                     | ResourceType::VOICE_STATE,
             )
@@ -196,9 +537,9 @@ impl Bot {
         let command_parser = {
             let mut parser_config = CommandParserConfig::new();
             parser_config.add_prefix("~");
-            parser_config.add_command("ident", false);
-            parser_config.add_command("check", false);
-            parser_config.add_command("stop", false);
+            for command in COMMANDS {
+                parser_config.add_command(*command, false);
+            }
 
             Arc::new(Parser::new(parser_config))
         };
@@ -209,18 +550,59 @@ impl Bot {
             gateway_events,
             discord_client,
             command_parser,
-            broadcast_channel,
-            living_channel,
-            dead_channel,
+            broadcast_channel: config.broadcast_channel,
+            settings: Settings::from_config(config),
+            profiles: config.profiles.clone(),
+            announce_unmatched: config.announce_unmatched,
+            announce_impostors: config.announce_impostors,
+            reveal_roles: config.reveal_roles,
+            hush_voting: config.hush_voting,
+            nickname_status: config.nickname_status,
+            status_board: config.status_board,
+            lounge_after_meetings: config.lounge_after_meetings,
+            afk_after_meetings: config.afk_after_meetings,
+            ident_ttl: Duration::from_secs(config.ident_ttl_days.saturating_mul(DAY_SECS)),
+            max_state_age: Duration::from_secs(config.max_state_age_secs),
+            kick_channel: config.kick_channel,
+            afk_channel: config.afk_channel,
+            afk_channel_after: Duration::from_secs(config.afk_channel_after_secs),
+            cue_channel: config.cue_channel,
+            kick_mute: config.kick_mute,
+            require_arming: config.require_arming,
+            mute_in_place: config.mute_in_place,
+            mute_pacing: config.mute_pacing,
+            mute_pacing_interval: Duration::from_millis(config.mute_pacing_ms),
+            failure_budget: config.failure_budget,
+            lobby_size: config.lobby_size,
+            archive_channel: config.archive_channel,
+            quiet_channels: config.quiet_channels.clone(),
+            muted_bots: config.muted_bots.clone(),
+            emoji: EmojiSet::new(&config.emoji),
+            matching: config.matching.clone(),
+            rules: config.rules.clone(),
+            quiet_slow_mode: config.quiet_slow_mode_secs,
+            session_idle: Duration::from_secs(config.session_idle_mins * 60),
+            lobby_cleanup: Duration::from_secs(config.lobby_cleanup_secs),
+            transcript_path: config.transcript_path.clone(),
+            transcript_upload: config.transcript_upload,
+            control_permission: config.control_permission,
+            ignored_command_feedback: config.ignored_command_feedback,
+            store_path: config.store_path.clone(),
+            lock_path: config.lock_path.clone(),
         }
     }
 
-    pub async fn start(&mut self, mut events: Events) -> Result<()> {
+    pub async fn start(
+        &mut self,
+        mut events: Events,
+        mut reports: UnboundedReceiver<Report>,
+    ) -> Result<()> {
         let shutdown_handle = self.discord_gateway.clone();
+        let supervisor = self.supervisor.clone();
 
         tokio::spawn(async move {
-            if let Err(why) = ctrl_c().await {
-                tracing::error!("There was an error registering the ctrl+c handler");
+            if let Err(why) = signals::shutdown().await {
+                tracing::error!("There was an error registering the shutdown signal handlers");
                 tracing::error!("{}", why);
             }
 
@@ -229,286 +611,4376 @@ impl Bot {
 
         self.discord_gateway.start().await?;
 
-        let mut bot = self.clone();
-        tokio::spawn(async move {
-            let mut bot_state = BotState::PreGame;
-            loop {
-                if let Err(why) = bot.game_state_rx.changed().await {
-                    tracing::error!("Game state receive failed: {}", why);
-                    break;
-                }
-                let state = bot.game_state_rx.borrow().as_ref().map(|s| (*s).clone());
-                match state {
-                    Some(State::InGame { meeting, .. })
-                        if matches!(
-                            meeting,
-                            MeetingState::Discussion
-                                | MeetingState::NotVoted
-                                | MeetingState::Voted
-                                | MeetingState::Results
-                        ) =>
-                    {
-                        // In a meeting
-                        if matches!(bot_state, BotState::PreGame | BotState::InGame) {
-                            bot_state = BotState::InMeeting;
-                            bot.start_meeting().await;
-                        }
-                    }
-                    Some(State::InGame { .. }) => {
-                        // In gameplay
-                        match bot_state {
-                            BotState::InMeeting => {
-                                bot.end_meeting(&mut bot_state).await;
-                            }
-                            BotState::PreGame => {
-                                bot_state = BotState::InGame;
-                                bot.start_game().await;
-                            }
-                            _ => {}
-                        }
-                    }
-                    Some(State::Lobby { .. } | State::Menu) | None => {
-                        // No game running or crash
-                        if matches!(bot_state, BotState::InGame | BotState::InMeeting) {
-                            bot_state = BotState::PreGame;
-                            bot.end_game().await;
-                        }
-
-                        if matches!(bot_state, BotState::GameOver) {
-                            bot_state = BotState::PreGame;
+        let bot = self.clone();
+        supervisor.spawn("session events", Policy::Restart, move || {
+            let bot = bot.clone();
+            let mut session_events = bot.session.subscribe();
+            async move {
+                loop {
+                    match session_events.recv().await {
+                        Ok(event) => bot.record_session_event(&event),
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Transcript missed {} session events", skipped);
                         }
+                        Err(RecvError::Closed) => return Ok(()),
                     }
                 }
             }
         });
 
-        while let Some(event) = events.next().await {
-            self.cache.update(&event);
+        if let (true, Some(guild_id)) = (self.nickname_status, self.home_guild) {
+            let bot = self.clone();
+            supervisor.spawn("nickname status", Policy::Restart, move || {
+                bot.clone().show_nickname_status(guild_id)
+            });
+        }
 
-            match event {
-                Event::MessageCreate(message) if !message.author.bot => {
-                    if let Err(why) = self.handle_command(&message).await {
-                        tracing::error!("An error occurred whilst processing a command!");
-                        tracing::error!("Message: {:?}", &message);
-                        tracing::error!("Error: {}", why);
-                    }
-                }
-                _ => {}
+        if let Some(channel_id) = self.broadcast_channel {
+            let announcer = self.announcer.clone();
+            let client = self.discord_client.clone();
+            supervisor.spawn("announcer", Policy::Restart, move || {
+                announcer.clone().run(client.clone(), channel_id)
+            });
+        }
+
+        match (self.status_board, self.broadcast_channel, self.home_guild) {
+            (true, Some(channel_id), Some(guild_id)) => {
+                let bot = self.clone();
+                supervisor.spawn("status board", Policy::Restart, move || {
+                    bot.clone().show_status_board(guild_id, channel_id)
+                });
             }
+            (true, None, _) => {
+                tracing::warn!(
+                    "status_board is set, but there is no broadcast channel to post it in"
+                );
+            }
+            _ => {}
         }
 
-        Ok(())
-    }
+        if let Some(channel_id) = self.cue_channel {
+            let bot = self.clone();
+            supervisor.spawn("spoken cues", Policy::Restart, move || {
+                bot.clone().speak_cues(channel_id)
+            });
+        }
 
-    async fn handle_command(&self, message: &Message) -> Result<()> {
-        match self.command_parser.parse(&message.content) {
-            Some(Command {
-                name: "ident",
-                mut arguments,
-                ..
-            }) => self.ident_player(&message, &mut arguments).await?,
-            Some(Command { name: "check", .. }) => self.check_matching(&message).await?,
-            Some(Command { name: "stop", .. }) => {
-                if self.owners.contains(&message.author.id) {
-                    self.discord_client
-                        .create_message(message.channel_id)
-                        .content("Good night")?
-                        .reply(message.id)
-                        .await?;
-                    self.discord_gateway.shutdown();
+        if !self.quiet_channels.is_empty() {
+            let bot = self.clone();
+            supervisor.spawn("quiet channels", Policy::Restart, move || {
+                bot.clone().quiet_text_channels()
+            });
+        }
+
+        if !self.rules.is_empty() {
+            let bot = self.clone();
+            supervisor.spawn("house rules", Policy::Restart, move || {
+                bot.clone().run_house_rules()
+            });
+        }
+
+        if self.archive_channel.is_some() {
+            let bot = self.clone();
+            supervisor.spawn("session summary", Policy::Restart, move || {
+                bot.clone().wrap_up_idle_sessions()
+            });
+        }
+
+        let bot = self.clone();
+        supervisor.spawn("state machine", Policy::Essential, move || {
+            bot.clone().run_state_machine()
+        });
+
+        let bot = self.clone();
+        tokio::spawn(async move {
+            while let Some(report) = reports.recv().await {
+                match report {
+                    Report::Died { task, reason } => {
+                        reporting::report(
+                            &format!("Background task {} died: {}", task, reason),
+                            &[("task", task.to_owned())],
+                        );
+                        let _e_msg = bot
+                            .notify(&format!(
+                                "Background task {} died: {}. It will keep being restarted, \
+                                 less often each time it fails",
+                                task, reason
+                            ))
+                            .await;
+                    }
+                    Report::Recovered { task } => {
+                        let _e_msg = bot
+                            .notify(&format!("Background task {} has recovered", task))
+                            .await;
+                    }
+                    Report::Fatal { task } => {
+                        reporting::report(
+                            &format!("{} could not be recovered", task),
+                            &[("task", task.to_owned())],
+                        );
+                        let _e_msg = bot
+                            .notify(&format!("{} could not be recovered, shutting down", task))
+                            .await;
+                        bot.discord_gateway.shutdown();
+                    }
                 }
             }
-            _ => {}
+        });
+
+        while let Some(event) = events.next().await {
+            self.handle_event(event).await;
+        }
+
+        if let Some(guild_id) = self.home_guild {
+            self.lease.release(guild_id)?;
         }
 
         Ok(())
     }
 
-    async fn start_meeting(&self) {
-        tracing::info!("Start meeting");
+    // Everything the bot does with a gateway event, kept apart from owning the gateway. Taskinator
+    // is only built as a binary, so this isn't offered to other bots to embed
+    async fn handle_event(&self, event: Event) {
+        let late_joiner = self.late_joiner(&event);
+        let unmuted = self.opted_out_unmute(&event);
+        self.cache.update(&event);
 
-        let mut futs = self
-            .match_members_to_players(&self.get_members_in_channel(self.living_channel))
-            .expect("failed to match players at start of meeting - this should not happen!")
-            .iter()
-            .filter_map(|(m, p)| match p {
-                Some(p) if !p.dead => Some(
-                    self.discord_client
-                        .update_guild_member(m.guild_id, m.user_id)
-                        .mute(false),
-                ),
-                _ => None,
-            })
-            .collect::<Vec<_>>();
+        if let Some(user_id) = unmuted {
+            if let Err(why) = self.remind_to_mute(user_id).await {
+                tracing::warn!("Failed to remind {} to mute: {}", user_id, why);
+            }
+        }
 
-        futs.extend(
-            self.get_members_in_channel(self.dead_channel)
-                .iter()
-                .map(|m| {
-                    self.discord_client
-                        .update_guild_member(m.guild_id, m.user_id)
-                        .channel_id(self.living_channel)
-                        .mute(true)
-                }),
-        );
+        if let Some((guild_id, user_id)) = late_joiner {
+            if let Err(why) = self.handle_late_joiner(guild_id, user_id).await {
+                tracing::error!("An error occurred whilst handling a late joiner!");
+                tracing::error!("Error: {}", why);
+            }
+        }
 
-        self.batch(futs).await;
+        match event {
+            Event::GuildCreate(guild) if Some(guild.0.id) == self.home_guild => {
+                self.renew_lease(false).await;
+                self.end_outage().await;
+                if let Err(why) = self.prime_cache(guild.0.id).await {
+                    tracing::warn!("Failed to fetch members in voice: {}", why);
+                }
+                if let Err(why) = self.warn_unmodifiable_members(guild.0.id).await {
+                    tracing::warn!("Failed to check the role hierarchy: {}", why);
+                }
+                if let Err(why) = self.check_emoji(guild.0.id).await {
+                    tracing::warn!("Failed to check the configured emoji: {}", why);
+                }
+            }
+            Event::GuildDelete(guild) if guild.unavailable && Some(guild.id) == self.home_guild => {
+                tracing::warn!("The home server became unavailable");
+                self.session
+                    .apply(SessionEvent::OutageStarted(Instant::now()));
+            }
+            Event::GuildCreate(guild) => {
+                if let Err(why) =
+                    welcome::onboard(&self.discord_client, &self.store, &guild.0).await
+                {
+                    tracing::error!("An error occurred whilst onboarding a guild!");
+                    tracing::error!("Error: {}", why);
+                }
+            }
+            Event::MessageCreate(message)
+                if !message.author.bot && self.handled_messages.lock().insert(message.id) =>
+            {
+                // A DM from them shows they can be DMed again
+                if message.guild_id.is_none() && self.store.user(message.author.id).dms_closed {
+                    if let Err(why) = self
+                        .store
+                        .update_user(message.author.id, |user| user.dms_closed = false)
+                    {
+                        tracing::warn!(
+                            "Failed to save that {} accepts DMs: {}",
+                            message.author.id,
+                            why
+                        );
+                    }
+                }
+                if let Err(why) = self.handle_command(&message).await {
+                    tracing::error!("An error occurred whilst processing a command!");
+                    tracing::error!("Message: {:?}", &message);
+                    tracing::error!("Error: {}", why);
+                }
+            }
+            Event::InteractionCreate(interaction) => {
+                if let Interaction::MessageComponent(component) = &interaction.0 {
+                    let custom_id = &component.data.custom_id;
+                    let handled = match (history::page_of(custom_id), panel::action_of(custom_id)) {
+                        (Some(page), _) => self.turn_history_page(component, page).await,
+                        (None, Some(action)) => self.handle_panel(component, action).await,
+                        (None, None) => self.handle_confirmation(component).await,
+                    };
+                    if let Err(why) = handled {
+                        tracing::error!("An error occurred whilst processing a button press!");
+                        tracing::error!("Error: {}", why);
+                    }
+                }
+            }
+            _ => {}
+        }
     }
 
-    async fn end_meeting(&self, bot_state: &mut BotState) {
-        tracing::info!("End meeting");
-
-        sleep(Duration::from_secs(10)).await;
-
-        let game_over = {
-            let state = self.game_state_rx.borrow();
+    async fn show_nickname_status(self, guild_id: GuildId) -> Result<()> {
+        // Discord only allows a few nickname changes a minute, so changes in between are coalesced
+        const COOLDOWN: Duration = Duration::from_secs(30);
 
-            match &*state {
-                Some(State::InGame { players, .. }) => {
-                    let (imposters, crew) = players
-                        .iter()
-                        .filter(|p| !p.dead)
-                        .partition::<Vec<_>, _>(|p| p.impostor);
+        let mut session_events = self.session.subscribe();
+        let mut shown = None;
 
-                    imposters.is_empty() || imposters.len() >= crew.len()
+        loop {
+            let status = if self.session.paused() {
+                "PAUSED"
+            } else if !self.is_armed() {
+                "DISARMED"
+            } else if self.is_degraded() {
+                "DEGRADED"
+            } else {
+                match self.session.phase() {
+                    Phase::PreGame => "LOBBY",
+                    Phase::InGame => "ROUND",
+                    Phase::InMeeting => "MEETING",
+                    Phase::GameOver => "GAME OVER",
                 }
-                _ => true,
+            };
+
+            if shown != Some(status) {
+                self.discord_client
+                    .update_current_user_nick(guild_id, format!("Taskinator [{}]", status))
+                    .await?;
+                shown = Some(status);
+                sleep(COOLDOWN).await;
+                continue;
             }
-        };
 
-        if game_over {
-            tracing::info!("Game is, in fact, over");
-            *bot_state = BotState::GameOver;
-            return self.end_game().await;
+            match session_events.recv().await {
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return Ok(()),
+            }
         }
+    }
 
-        *bot_state = BotState::InGame;
+    // Refreshed on every session event and otherwise every so often for the stats, but Discord
+    // rate limits edits, so changes in between are coalesced
+    async fn show_status_board(self, guild_id: GuildId, channel_id: ChannelId) -> Result<()> {
+        const COOLDOWN: Duration = Duration::from_secs(30);
 
-        self.mute_players().await;
-    }
+        let mut session_events = self.session.subscribe();
+        let mut refresh = interval(COOLDOWN);
+        let mut shown = None;
 
-    async fn start_game(&self) {
-        tracing::info!("START GAME!");
+        loop {
+            let embed = self.status_board_embed(guild_id)?;
+            if shown.as_ref() != Some(&embed) {
+                self.post_status_board(guild_id, channel_id, embed.clone())
+                    .await?;
+                shown = Some(embed);
+                sleep(COOLDOWN).await;
+            }
 
-        self.mute_players().await;
+            tokio::select! {
+                received = session_events.recv() => {
+                    if let Err(RecvError::Closed) = received {
+                        return Ok(());
+                    }
+                }
+                _ = refresh.tick() => {}
+            }
+        }
     }
 
-    async fn end_game(&self) {
-        tracing::info!("End game");
+    fn status_board_embed(&self, guild_id: GuildId) -> Result<Embed> {
+        // Keeps each line of the recent warnings field well within Discord's limit for a field
+        const MAX_RECORD_LENGTH: usize = 300;
+        const MAX_RECORDS: usize = 3;
 
-        let mut futs = self
-            .get_members_in_channel(self.living_channel)
-            .iter()
-            .map(|m| {
-                self.discord_client
-                    .update_guild_member(m.guild_id, m.user_id)
-                    .mute(false)
+        let tasks = self
+            .supervisor
+            .tasks()
+            .into_iter()
+            .map(|(task, info)| {
+                format!(
+                    "{}: {}, restarted {} times",
+                    task, info.status, info.restarts
+                )
             })
             .collect::<Vec<_>>();
 
-        futs.extend(
-            self.get_members_in_channel(self.dead_channel)
-                .iter()
-                .map(|m| {
-                    self.discord_client
-                        .update_guild_member(m.guild_id, m.user_id)
-                        .channel_id(self.living_channel)
-                }),
-        );
-
-        self.batch(futs).await;
-    }
-
-    async fn mute_players(&self) {
-        let futs = self
-            .match_members_to_players(&self.get_members_in_channel(self.living_channel))
-            .expect("failed to match players at end of meeting - this should not happen!")
+        let records = self.logs.tail(Level::WARN, Duration::from_secs(60 * 60));
+        let warnings = records
             .iter()
-            .filter_map(|(m, p)| match p {
-                Some(p) if p.dead => Some(
-                    self.discord_client
-                        .update_guild_member(m.guild_id, m.user_id)
-                        .channel_id(self.dead_channel)
-                        .mute(false),
-                ),
-                Some(p) if !p.dead => Some(
-                    self.discord_client
-                        .update_guild_member(m.guild_id, m.user_id)
-                        .mute(true),
-                ),
-                _ => None,
-            })
+            .skip(records.len().saturating_sub(MAX_RECORDS))
+            .map(|record| record.chars().take(MAX_RECORD_LENGTH).collect::<String>())
             .collect::<Vec<_>>();
 
-        self.batch(futs).await;
+        let embed = self
+            .status_embed(Some(guild_id))
+            .title("Status")
+            .field(EmbedFieldBuilder::new("Tasks", tasks.join("\n")).build())
+            .field(
+                EmbedFieldBuilder::new(
+                    "Warnings in the last hour",
+                    if warnings.is_empty() {
+                        "None".to_owned()
+                    } else {
+                        warnings.join("\n")
+                    },
+                )
+                .build(),
+            );
+
+        Ok(embed.build()?)
     }
 
-    async fn ident_player(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
-        match arguments.next() {
-            Some(argument) => {
-                if let Ok(target) = UserId::parse(argument) {
-                    if self.owners.contains(&message.author.id) {
-                        if let Some(ign) = arguments.next() {
-                            self.player_names.write().insert(target, ign.to_owned());
-                            message
-                                .reply(
-                                    &self.discord_client,
-                                    format!("Set {}'s IGN to {}", target.mention(), ign),
-                                )?
-                                .await?;
-                        } else {
-                            message
-                                .reply(&self.discord_client, "You must include an in game name")?
-                                .await?;
-                        }
-                    } else {
-                        message
-                            .reply(
-                                &self.discord_client,
-                                "Only owners can set the in game name of another user",
-                            )?
-                            .await?;
-                    }
-                } else {
-                    self.player_names
-                        .write()
-                        .insert(message.author.id, argument.to_owned());
-                    message
-                        .reply(
-                            &self.discord_client,
-                            format!("Set your in game name to {}", argument),
-                        )?
-                        .await?;
+    // Edits the board posted before, even before a restart, or posts and pins a new one if it has
+    // been deleted
+    async fn post_status_board(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        embed: Embed,
+    ) -> Result<()> {
+        if let Some(board) = self.store.guild(guild_id).status_board {
+            match self
+                .discord_client
+                .update_message(channel_id, board)
+                .embed(Some(embed.clone()))?
+                .await
+            {
+                Ok(_) => return Ok(()),
+                Err(why) if is_not_found(&why) => {
+                    tracing::info!("The status board was deleted, posting a new one");
                 }
+                Err(why) => return Err(why.into()),
             }
-            None => {
-                message
-                    .reply(&self.discord_client, "Please include your in game name")?
-                    .await?;
-            }
-        };
+        }
+
+        let board = self
+            .discord_client
+            .create_message(channel_id)
+            .embed(embed)?
+            .await?;
+        self.store
+            .update_guild(guild_id, |guild| guild.status_board = Some(board.id))?;
+
+        // Without Manage Messages the board still works, it's just harder to find
+        if let Err(why) = self.discord_client.create_pin(channel_id, board.id).await {
+            tracing::warn!("Failed to pin the status board: {}", why);
+        }
 
         Ok(())
     }
 
-    async fn check_matching(&self, message: &Message) -> Result<()> {
-        match self.match_members_to_players(&self.get_members_in_channel(self.living_channel)) {
-            Some(matched_players) => {
-                tracing::trace!("{:?}", matched_players);
-                let unmatched_players = matched_players
-                    .into_iter()
+    // For players whose game is still loading the meeting screen, or back from it, when the mutes
+    // change. Kept off the state machine so the mutes never wait on the messages
+    async fn speak_cues(self, channel_id: ChannelId) -> Result<()> {
+        let mut session_events = self.session.subscribe();
+        let mut phase = self.session.phase();
+
+        loop {
+            let next = match session_events.recv().await {
+                Ok(SessionEvent::PhaseChanged(next)) => next,
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            let cue = match (phase, next) {
+                (Phase::InMeeting, Phase::InMeeting) => None,
+                (_, Phase::InMeeting) => Some("Meeting started"),
+                (Phase::InMeeting, Phase::InGame) => Some("Back to tasks"),
+                _ => None,
+            };
+            phase = next;
+
+            if let (Some(cue), true) = (cue, self.is_active()) {
+                self.discord_client
+                    .create_message(channel_id)
+                    .content(cue)?
+                    .tts(true)
+                    .await?;
+            }
+        }
+    }
+
+    // Runs the house rules from the config against the session's events. Deaths and meetings are
+    // counted afresh each game, so a rule can pick out e.g. the first death
+    async fn run_house_rules(self) -> Result<()> {
+        let mut session_events = self.session.subscribe();
+        let mut phase = self.session.phase();
+        let mut dead = HashSet::new();
+        let (mut deaths, mut meetings) = (0, 0);
+
+        // Roles still listed outside a game were given before a restart and never taken back
+        if matches!(phase, Phase::PreGame | Phase::GameOver) {
+            self.take_back_roles().await;
+        }
+
+        loop {
+            let event = match session_events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            let mut fired = Vec::new();
+            match event {
+                SessionEvent::PhaseChanged(next) => {
+                    let previous = phase;
+                    phase = next;
+
+                    match (previous, next) {
+                        (Phase::PreGame | Phase::GameOver, Phase::InGame) => {
+                            dead.clear();
+                            deaths = 0;
+                            meetings = 0;
+                            fired.push((Trigger::GameStart, 1, None));
+                        }
+                        (Phase::PreGame | Phase::InGame, Phase::InMeeting) => {
+                            meetings += 1;
+                            fired.push((Trigger::Meeting, meetings, None));
+                        }
+                        (Phase::InGame | Phase::InMeeting, Phase::PreGame | Phase::GameOver) => {
+                            self.take_back_roles().await;
+                            fired.push((Trigger::GameEnd, 1, None));
+                        }
+                        _ => {}
+                    }
+                }
+                // Deaths only count once confirmed, by a meeting or by polls agreeing
+                SessionEvent::DeadSeen(names)
+                | SessionEvent::MeetingStarted { dead: names, .. } => {
+                    let mut names = names
+                        .into_iter()
+                        .filter(|name| self.session.death_confirmed(name) && !dead.contains(name))
+                        .collect::<Vec<_>>();
+                    names.sort();
+
+                    for name in names {
+                        dead.insert(name.clone());
+                        deaths += 1;
+                        fired.push((Trigger::Death, deaths, Some(name)));
+                    }
+                }
+                _ => continue,
+            }
+
+            if !self.is_active() {
+                continue;
+            }
+
+            for (trigger, count, player) in fired {
+                for rule in self
+                    .rules
+                    .iter()
+                    .filter(|rule| rule.applies(trigger, count))
+                {
+                    self.apply_house_rule(rule, player.as_deref()).await;
+                }
+            }
+        }
+    }
+
+    async fn apply_house_rule(&self, rule: &HouseRule, player: Option<&str>) {
+        tracing::info!("Applying a house rule on {:?}", rule.on);
+
+        if let (Some(message), true) = (rule.message(player), self.broadcast_channel.is_some()) {
+            self.announcer.send(Announcement::text(message));
+        }
+
+        if let (Some(role_id), Some(member)) = (
+            rule.give_role,
+            player.and_then(|name| self.member_playing(name)),
+        ) {
+            let result = self
+                .discord_client
+                .send("add_member_role", || {
+                    self.discord_client.add_guild_member_role(
+                        member.guild_id,
+                        member.user_id,
+                        role_id,
+                    )
+                })
+                .await;
+            match result {
+                Ok(_) => {
+                    let saved = self.store.update_guild(member.guild_id, |guild| {
+                        guild.rule_roles.push((member.user_id, role_id));
+                    });
+                    if let Err(why) = saved {
+                        tracing::warn!("Failed to save a house rule role: {}", why);
+                    }
+                }
+                Err(why) => tracing::warn!("Failed to give a house rule role: {}", why),
+            }
+        }
+
+        if rule.unmute_ghosts {
+            self.session.apply(SessionEvent::GhostsUnmuted(true));
+
+            // The dead already muted for this meeting are let in now, rather than at the next one
+            if self.session.phase() == Phase::InMeeting {
+                if let Some(plan) = self.reconcile_plan() {
+                    self.execute(plan).await;
+                }
+            }
+        }
+    }
+
+    async fn take_back_roles(&self) {
+        let guild_id = match self.home_guild {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+
+        let given = match self
+            .store
+            .update_guild(guild_id, |guild| std::mem::take(&mut guild.rule_roles))
+        {
+            Ok(given) => given,
+            Err(why) => {
+                tracing::warn!("Failed to take back house rule roles: {}", why);
+                return;
+            }
+        };
+
+        for (user_id, role_id) in given {
+            let result = self
+                .discord_client
+                .send("remove_member_role", || {
+                    self.discord_client
+                        .remove_guild_member_role(guild_id, user_id, role_id)
+                })
+                .await;
+            if let Err(why) = result {
+                tracing::warn!("Failed to take back a house rule role: {}", why);
+            }
+        }
+    }
+
+    // Mirrors the voice rules in text: slow mode while playing, lifted for meetings and between games
+    async fn quiet_text_channels(self) -> Result<()> {
+        let mut session_events = self.session.subscribe();
+
+        loop {
+            let quiet = self.is_active() && self.session.phase() == Phase::InGame;
+            // Slow mode each channel had before the bot changed it, restored when lifting
+            let previous = self.store.slow_modes();
+
+            if quiet && previous.is_empty() {
+                for &channel_id in self.quiet_channels.iter() {
+                    let rate_limit = match self.discord_client.channel(channel_id).await? {
+                        Some(Channel::Guild(GuildChannel::Text(channel))) => {
+                            channel.rate_limit_per_user.unwrap_or(0)
+                        }
+                        _ => {
+                            tracing::warn!("Quiet channel {} is not a text channel", channel_id);
+                            continue;
+                        }
+                    };
+
+                    // Saved first, so it's restored even if the bot stops right after the change
+                    self.store.set_slow_mode(channel_id, Some(rate_limit))?;
+                    self.discord_client
+                        .update_channel(channel_id)
+                        .rate_limit_per_user(self.quiet_slow_mode.max(rate_limit))?
+                        .await?;
+                }
+            } else if !quiet {
+                for (channel_id, rate_limit) in previous {
+                    self.discord_client
+                        .update_channel(channel_id)
+                        .rate_limit_per_user(rate_limit)?
+                        .await?;
+                    self.store.set_slow_mode(channel_id, None)?;
+                }
+            }
+
+            match session_events.recv().await {
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    async fn wrap_up_idle_sessions(self) -> Result<()> {
+        loop {
+            sleep(Duration::from_secs(60)).await;
+
+            let idle = self
+                .summary
+                .lock()
+                .as_ref()
+                .map_or(false, |summary| summary.is_idle(self.session_idle));
+            if idle {
+                self.post_summary().await?;
+            }
+        }
+    }
+
+    // Returns whether there was anything to post
+    async fn post_summary(&self) -> Result<bool> {
+        let archive_channel = match self.archive_channel {
+            Some(archive_channel) => archive_channel,
+            None => return Ok(false),
+        };
+
+        let digest = match self.summary.lock().as_ref() {
+            Some(summary) => summary.digest(),
+            None => return Ok(false),
+        };
+
+        let embed = EmbedBuilder::new()
+            .title("Session summary")
+            .description(digest)
+            .color(0x00_80_FF);
+
+        self.discord_client
+            .create_message(archive_channel)
+            .embed(embed.build()?)?
+            .await?;
+
+        // Only ended once it's been posted, so a failed post can be tried again
+        self.summary.lock().take();
+
+        Ok(true)
+    }
+
+    async fn run_state_machine(mut self) -> Result<()> {
+        // Updates from the watcher can be missed entirely, so every so often voice states are
+        // checked against what they should be right now
+        const RECONCILE_INTERVAL: Duration = Duration::from_secs(15);
+        // Gives Discord time to report the updates from a transition before checking them
+        const SETTLE: Duration = Duration::from_secs(5);
+
+        let mut reconcile = interval(RECONCILE_INTERVAL);
+        let mut last_transition = Instant::now();
+        let mut idle_since = None;
+        // Cleaned up once per stuck game, members it can't unmute mustn't end it over and over
+        let mut cleaned_up = false;
+
+        // The last update acted on, the next is diffed against it
+        let mut previous = None;
+        let mut differ = Differ::default();
+
+        // Correlates the logs of everything done for one transition, including each member update
+        let mut game_id = 0_u64;
+        let mut transition_id = 0_u64;
+        let mut span = |kind: &'static str, game_id: u64| {
+            transition_id += 1;
+            tracing::info_span!(
+                "transition",
+                game = game_id,
+                transition = transition_id,
+                kind
+            )
+        };
+
+        loop {
+            tokio::select! {
+                changed = self.game_state_rx.changed() => {
+                    if let Err(why) = changed {
+                        tracing::error!("Game state receive failed: {}", why);
+                        return Err(why.into());
+                    }
+                }
+                _ = reconcile.tick() => {
+                    self.renew_lease(false).await;
+                    if self.session.outage_since().is_some() {
+                        self.check_outage_over().await;
+                        continue;
+                    }
+                    if self.is_active() {
+                        if let Err(why) = self.park_departed().await {
+                            tracing::warn!("Failed to move players to the AFK channel: {}", why);
+                        }
+                    }
+                    if self.is_active() && self.left_muted() {
+                        let since = *idle_since.get_or_insert_with(Instant::now);
+                        if !cleaned_up && since.elapsed() >= self.lobby_cleanup {
+                            tracing::warn!("Players were left muted outside of a game, ending it");
+                            self.force_end_game()
+                                .instrument(span("lobby cleanup", game_id))
+                                .await;
+                            cleaned_up = true;
+                        }
+                    } else {
+                        idle_since = None;
+                        cleaned_up = false;
+                    }
+                    if self.is_active() && last_transition.elapsed() >= SETTLE {
+                        if let Some(plan) = self.reconcile_plan() {
+                            if !plan.is_empty() {
+                                tracing::warn!("Voice states drifted from the game, reconciling");
+                                self.execute(plan)
+                                    .instrument(span("reconcile", game_id))
+                                    .await;
+                            }
+                        }
+                    }
+                    continue;
+                }
+            }
+            let received = Instant::now();
+
+            if !self.is_active() {
+                continue;
+            }
+
+            let snapshot = self.game_state_rx.borrow().clone();
+            // If this task stalled, act on the next update rather than an outdated phase
+            if let Some(snapshot) = &snapshot {
+                let age = snapshot.read_at.elapsed();
+                if age > self.max_state_age {
+                    tracing::warn!("Ignoring a game state read {:?} ago", age);
+                    continue;
+                }
+            }
+
+            let state = snapshot.map(|s| s.state);
+            self.states.lock().record(state.as_ref());
+            let lost = state.is_none();
+            let changes = differ.diff(previous.as_ref(), state.as_ref());
+
+            if let Some(State::InGame { players, .. }) = &state {
+                if let Some(transcript) = self.transcript.lock().as_mut() {
+                    transcript.record_players(players);
+                }
+
+                self.session.apply(SessionEvent::DeadSeen(
+                    players
+                        .iter()
+                        .filter(|p| p.dead)
+                        .map(|p| p.name.clone())
+                        .collect(),
+                ));
+
+                if matches!(self.session.phase(), Phase::InGame | Phase::InMeeting) {
+                    self.track_departures();
+                }
+            }
+
+            for change in changes {
+                let phase = self.session.phase();
+
+                match change {
+                    Change::PlayerJoined(name) => tracing::info!("{} joined the game", name),
+                    Change::PlayerLeft(name) => tracing::info!("{} left the game", name),
+                    Change::PlayerDied(name) => tracing::info!("{} died", name),
+                    Change::MeetingStarted => {
+                        // In a meeting, once the game reports it a forced meeting ends with it
+                        self.session.apply(SessionEvent::MeetingForced(false));
+                        if matches!(phase, Phase::PreGame | Phase::InGame) {
+                            self.session
+                                .apply(SessionEvent::PhaseChanged(Phase::InMeeting));
+                            if let Err(why) = self
+                                .start_meeting()
+                                .instrument(span("meeting start", game_id))
+                                .await
+                            {
+                                tracing::warn!("Failed to start meeting: {}", why);
+                            }
+                            self.telemetry.record_transition(received.elapsed());
+                            last_transition = Instant::now();
+                        }
+                    }
+                    Change::MeetingEnded => {
+                        if phase == Phase::InMeeting && !self.session.meeting_forced() {
+                            // The configured delay is deliberate, only count the time on top of it
+                            let delay = self.settings().meeting_end_delay;
+                            self.end_meeting()
+                                .instrument(span("meeting end", game_id))
+                                .await;
+                            self.telemetry
+                                .record_transition(received.elapsed().saturating_sub(delay));
+                            last_transition = Instant::now();
+                        }
+                    }
+                    Change::VotingStarted => {
+                        if self.hush_voting && phase == Phase::InMeeting {
+                            if let Some(plan) = self.voting_plan() {
+                                self.execute(plan)
+                                    .instrument(span("voting start", game_id))
+                                    .await;
+                            }
+                        }
+                    }
+                    Change::GameStarted => {
+                        // After ~endgame in the lobby the bot is left in GameOver
+                        if matches!(phase, Phase::PreGame | Phase::GameOver) {
+                            self.session
+                                .apply(SessionEvent::PhaseChanged(Phase::InGame));
+                            game_id += 1;
+                            self.start_game()
+                                .instrument(span("game start", game_id))
+                                .await;
+                            self.telemetry.record_transition(received.elapsed());
+                            last_transition = Instant::now();
+                        }
+                    }
+                    Change::GameEnded => {
+                        match phase {
+                            // Quick rematches can go straight from one game to the next without a lobby
+                            Phase::InGame | Phase::InMeeting
+                                if matches!(state, Some(State::InGame { .. })) =>
+                            {
+                                tracing::info!("A new game started without returning to the lobby");
+                                self.session
+                                    .apply(SessionEvent::PhaseChanged(Phase::GameOver));
+                                self.end_game().instrument(span("game end", game_id)).await;
+                                self.session
+                                    .apply(SessionEvent::PhaseChanged(Phase::PreGame));
+                            }
+                            Phase::InGame | Phase::InMeeting => {
+                                if lost {
+                                    self.telemetry.record_source_failure();
+                                }
+                                self.session
+                                    .apply(SessionEvent::PhaseChanged(Phase::PreGame));
+                                self.end_game().instrument(span("game end", game_id)).await;
+                                self.telemetry.record_transition(received.elapsed());
+                                last_transition = Instant::now();
+                            }
+                            Phase::GameOver => {
+                                self.session
+                                    .apply(SessionEvent::PhaseChanged(Phase::PreGame));
+                            }
+                            Phase::PreGame => {}
+                        }
+
+                        // A lost game source says nothing about how the game went
+                        if let (false, Some(State::InGame { players, .. })) = (lost, &previous) {
+                            if self.reveal_roles {
+                                if let Err(why) = self.reveal_impostors(players) {
+                                    tracing::warn!("Failed to reveal the impostors: {}", why);
+                                }
+                            }
+
+                            // Wins by vote or by kill, whichever way the game ended up back in
+                            // the lobby
+                            if let Some(winner) = Team::from_survivors(players) {
+                                if let Err(why) = self.celebrate(winner) {
+                                    tracing::warn!("Failed to celebrate the win: {}", why);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            previous = state;
+        }
+    }
+
+    #[cfg(feature = "telemetry")]
+    pub fn telemetry(&self) -> Arc<Telemetry> {
+        self.telemetry.clone()
+    }
+
+    pub fn phase(&self) -> &'static str {
+        self.session.phase().name()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.session.paused()
+    }
+
+    #[cfg(feature = "voice-status")]
+    pub fn game_phase(&self) -> Phase {
+        self.session.phase()
+    }
+
+    #[cfg(feature = "voice-status")]
+    pub fn living_channel(&self) -> ChannelId {
+        self.settings().living_channel
+    }
+
+    #[cfg(feature = "voice-status")]
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<SessionEvent> {
+        self.session.subscribe()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.session.apply(SessionEvent::PauseChanged(paused));
+    }
+
+    // Without require_arming the bot is always armed, otherwise only on the living channel it was
+    // armed on. Either way it isn't while another instance holds the server
+    fn is_armed(&self) -> bool {
+        self.locked_out.lock().is_none()
+            && (!self.require_arming
+                || self.home_guild.map_or(false, |guild_id| {
+                    self.store.guild(guild_id).armed_channel == Some(self.settings().living_channel)
+                }))
+    }
+
+    // Every running instance sees the command, so it names the one that should take over
+    async fn take_over(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        let reply = match arguments.next() {
+            Some(instance) if instance == self.lease.instance() => {
+                self.renew_lease(true).await;
+                format!("Instance {} now holds the server", instance)
+            }
+            Some(_) => return Ok(()),
+            None => format!(
+                "Use ~takeover {} to make this instance hold the server",
+                self.lease.instance()
+            ),
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    async fn renew_lease(&self, force: bool) {
+        let guild_id = match self.home_guild {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+
+        let held_by = match self.lease.claim(guild_id, force) {
+            Ok(held_by) => held_by,
+            Err(why) => {
+                tracing::warn!("Failed to renew the instance lease: {}", why);
+                return;
+            }
+        };
+
+        let previous = std::mem::replace(&mut *self.locked_out.lock(), held_by.clone());
+        match (previous, held_by) {
+            (None, Some(other)) => {
+                tracing::error!("Instance {} is already running against the server", other);
+                let _e_msg = self
+                    .notify(&format!(
+                        "Another instance of the bot ({}) is running against this server, so \
+                         this one is disarmed. Stop the other, or use ~takeover {}",
+                        other,
+                        self.lease.instance()
+                    ))
+                    .await;
+            }
+            (Some(other), None) => {
+                tracing::info!("Took over the server from instance {}", other);
+                let _e_msg = self
+                    .notify(&format!(
+                        "This instance of the bot ({}) took over from {}",
+                        self.lease.instance(),
+                        other
+                    ))
+                    .await;
+            }
+            _ => {}
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        !self.session.paused() && self.is_armed()
+    }
+
+    pub fn roster(&self) -> Vec<RosterEntry> {
+        let mut members = self.get_living_members();
+        members.extend(self.get_members_in_channel(self.settings().dead_channel));
+        let matches = self.match_members_to_players(&members).unwrap_or_default();
+
+        self.current_players()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|player| RosterEntry {
+                user_id: matches
+                    .iter()
+                    .find(|(_, p)| p.as_ref().map_or(false, |p| p.name == player.name))
+                    .map(|(m, _)| m.user_id),
+                name: player.name,
+                dead: player.dead,
+            })
+            .collect()
+    }
+
+    // Returns false if there is no game to start
+    #[cfg(feature = "grpc")]
+    pub async fn force_start_game(&self) -> bool {
+        if !matches!(
+            self.game_state_rx.borrow().as_ref().map(|s| &s.state),
+            Some(State::InGame { .. })
+        ) {
+            return false;
+        }
+
+        self.session
+            .apply(SessionEvent::PhaseChanged(Phase::InGame));
+        self.start_game().await;
+
+        true
+    }
+
+    // The game can be back in the lobby without the bot having seen it end, e.g. the host quit
+    // mid-round while the watcher was down, leaving whoever it muted muted
+    fn left_muted(&self) -> bool {
+        let idle = matches!(
+            self.game_state_rx
+                .borrow()
+                .as_ref()
+                .map(|snapshot| &snapshot.state),
+            Some(State::Lobby { .. } | State::Menu)
+        );
+        if !idle {
+            return false;
+        }
+
+        self.settings()
+            .channels()
+            .into_iter()
+            .flat_map(|channel| self.get_members_in_channel(channel))
+            .any(|m| {
+                self.discord_client.muted_by_bot(m.guild_id, m.user_id)
+                    && self
+                        .cache
+                        .voice_state(m.user_id, m.guild_id)
+                        .map_or(false, |vs| vs.mute)
+            })
+    }
+
+    pub async fn force_end_game(&self) {
+        self.session
+            .apply(SessionEvent::PhaseChanged(Phase::GameOver));
+        self.end_game().await;
+    }
+
+    // Must be called before the cache is updated with the event, so the member's previous channel
+    // is known
+    fn late_joiner(&self, event: &Event) -> Option<(GuildId, UserId)> {
+        let voice_state = match event {
+            Event::VoiceStateUpdate(update) => &update.0,
+            _ => return None,
+        };
+        let guild_id = voice_state.guild_id?;
+
+        let living_channels = self.living_channels();
+        let is_living = |channel: Option<ChannelId>| {
+            channel.map_or(false, |channel| living_channels.contains(&channel))
+        };
+
+        let joined_living = is_living(voice_state.channel_id)
+            && self
+                .cache
+                .voice_state(voice_state.user_id, guild_id)
+                .map_or(true, |previous| !is_living(previous.channel_id));
+
+        if joined_living && self.session.phase() == Phase::InGame {
+            Some((guild_id, voice_state.user_id))
+        } else {
+            None
+        }
+    }
+
+    // Opted out members aren't server muted, so remind them when they unmute themselves, or join
+    // unmuted, while living players should be quiet. Also must be called before the cache update.
+    fn opted_out_unmute(&self, event: &Event) -> Option<UserId> {
+        let voice_state = match event {
+            Event::VoiceStateUpdate(update) => &update.0,
+            _ => return None,
+        };
+        let guild_id = voice_state.guild_id?;
+
+        if voice_state.self_mute
+            || self.session.phase() != Phase::InGame
+            || !self.is_active()
+            || !self.is_opted_out(voice_state.user_id)
+            || !voice_state
+                .channel_id
+                .map_or(false, |channel| self.living_channels().contains(&channel))
+        {
+            return None;
+        }
+
+        let was_muted = self
+            .cache
+            .voice_state(voice_state.user_id, guild_id)
+            .map_or(true, |previous| {
+                previous.self_mute || previous.channel_id != voice_state.channel_id
+            });
+
+        if was_muted {
+            Some(voice_state.user_id)
+        } else {
+            None
+        }
+    }
+
+    fn is_opted_out(&self, user_id: UserId) -> bool {
+        self.store.user(user_id).opt_out
+    }
+
+    async fn remind_to_mute(&self, user_id: UserId) -> Result<()> {
+        let content = format!(
+            "{} you have opted out of being muted, please mute yourself during rounds",
+            user_id.mention()
+        );
+
+        match self.broadcast_channel {
+            Some(_) => self.announcer.send(Announcement::text(content)),
+            None => {
+                self.dm(user_id, content).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Soft enforcement for opted out players at the start of a round
+    async fn remind_opted_out(&self) {
+        let members = self.get_living_members();
+        let reminders = self
+            .match_members_to_players(&members)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(m, p)| {
+                p.as_ref().map_or(false, |p| !p.dead)
+                    && self.is_opted_out(m.user_id)
+                    && self
+                        .cache
+                        .voice_state(m.user_id, m.guild_id)
+                        .map_or(false, |vs| !vs.self_mute)
+            })
+            .map(|(m, _)| self.remind_to_mute(m.user_id));
+
+        for result in futures::future::join_all(reminders).await {
+            if let Err(why) = result {
+                tracing::warn!("Failed to send a mute reminder: {}", why);
+            }
+        }
+    }
+
+    async fn handle_late_joiner(&self, guild_id: GuildId, user_id: UserId) -> Result<()> {
+        if !self.is_active()
+            || self
+                .cache
+                .user(user_id)
+                .map_or(true, |user| self.is_excluded(&user))
+        {
+            return Ok(());
+        }
+
+        // Spectators stepping out and back in are still only watching
+        if self.session.is_spectating(user_id) {
+            return Ok(());
+        }
+
+        // Without Move Members they're only left out of the automation, wherever they are
+        if self.session.is_parked(user_id) {
+            if let (Some(afk_channel), false) = (self.afk_channel, self.mute_in_place) {
+                self.discord_client
+                    .update_member(
+                        MemberUpdate::new(guild_id, user_id, "taskinator: AFK, use ~back")
+                            .channel_id(afk_channel),
+                    )
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        if self.session.is_kicked(user_id) {
+            if let (Some(kick_channel), false) = (self.kick_channel, self.mute_in_place) {
+                self.discord_client
+                    .update_member(
+                        MemberUpdate::new(guild_id, user_id, "taskinator: kicked from session")
+                            .channel_id(kick_channel),
+                    )
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let member = match self.cache.member(guild_id, user_id) {
+            Some(member) => member,
+            None => return Ok(()),
+        };
+
+        // Players rejoining voice are handled as usual, the policy only applies to everyone else
+        let player = self
+            .match_members_to_players(&[member])
+            .and_then(|mut matches| matches.pop())
+            .and_then(|(_, player)| player);
+
+        tracing::info!("{} joined the living channel mid-round", user_id);
+
+        let settings = self.settings();
+        match (player, settings.late_joiner_policy) {
+            (Some(player), _) if player.dead => {
+                self.discord_client
+                    .update_member(self.bury(
+                        MemberUpdate::new(guild_id, user_id, "taskinator: player died"),
+                        user_id,
+                        settings.dead_channel,
+                    ))
+                    .await?;
+            }
+            (Some(_), _) if self.is_opted_out(user_id) => self.remind_to_mute(user_id).await?,
+            (Some(_), _) | (None, LateJoinerPolicy::Mute) => {
+                self.discord_client
+                    .update_member(
+                        MemberUpdate::new(guild_id, user_id, "taskinator: joined mid-round")
+                            .mute(true),
+                    )
+                    .await?;
+            }
+            (None, LateJoinerPolicy::MoveToDead) => {
+                let update = MemberUpdate::new(guild_id, user_id, "taskinator: joined mid-round");
+                // Kept out of the round where they are, the same way as the dead
+                let update = if self.mute_in_place {
+                    update.mute(true).deaf(true)
+                } else {
+                    update.channel_id(settings.dead_channel)
+                };
+                self.discord_client.update_member(update).await?;
+            }
+            (None, LateJoinerPolicy::Ignore) => {}
+        }
+
+        Ok(())
+    }
+
+    async fn handle_command(&self, message: &Message) -> Result<()> {
+        match self.command_parser.parse(&message.content) {
+            Some(Command { name, .. }) if self.is_disabled(message, name) => {
+                self.ignored(
+                    message,
+                    Ignored::Denied("That command is turned off in this server"),
+                )
+                .await?;
+            }
+            Some(Command {
+                name: "ident",
+                mut arguments,
+                ..
+            }) => self.ident_player(&message, &mut arguments).await?,
+            Some(Command {
+                name: "forcematch",
+                mut arguments,
+                ..
+            }) => self.force_match(message, &mut arguments).await?,
+            Some(Command {
+                name: "swap",
+                mut arguments,
+                ..
+            }) => self.swap_players(message, &mut arguments).await?,
+            Some(Command { name: "check", .. }) => self.check_matching(&message).await?,
+            Some(Command {
+                name: "claim",
+                mut arguments,
+                ..
+            }) => self.claim_player(message, &mut arguments).await?,
+            Some(Command { name: "status", .. }) => self.show_status(message).await?,
+            Some(Command {
+                name: "diag",
+                mut arguments,
+                ..
+            }) => match arguments.next() {
+                Some("bundle") => self.send_diag_bundle(message).await?,
+                _ => self.show_diagnostics(message).await?,
+            },
+            Some(Command { name: "pause", .. }) if self.can_control(message.author.id) => {
+                message
+                    .reply(&self.discord_client, self.toggle_pause())?
+                    .await?;
+            }
+            Some(Command {
+                name: "controls", ..
+            }) if self.can_control(message.author.id) => {
+                self.discord_client
+                    .create_message(message.channel_id)
+                    .content(panel::TITLE)?
+                    .components(panel::buttons())?
+                    .await?;
+            }
+            Some(Command {
+                name: "endgame", ..
+            }) if self.can_control(message.author.id) => {
+                self.confirm(message, Action::EndGame).await?;
+            }
+            Some(Command { name: "wrapup", .. }) if self.can_control(message.author.id) => {
+                let reply = if self.archive_channel.is_none() {
+                    "No archive channel is configured"
+                } else if self.post_summary().await? {
+                    "Session summary posted"
+                } else {
+                    "No games have been played this session"
+                };
+                message.reply(&self.discord_client, reply)?.await?;
+            }
+            Some(Command {
+                name: "arm",
+                mut arguments,
+                ..
+            }) if self.can_control(message.author.id) => self.arm(message, &mut arguments).await?,
+            Some(Command { name: "disarm", .. }) if self.can_control(message.author.id) => {
+                self.disarm(message).await?;
+            }
+            Some(Command {
+                name: "meeting", ..
+            }) if self.can_control(message.author.id) => {
+                let reply = self.toggle_meeting().await;
+                message.reply(&self.discord_client, reply)?.await?;
+            }
+            Some(Command { name: "gather", .. }) if self.can_control(message.author.id) => {
+                self.gather(message).await?;
+            }
+            Some(Command {
+                name:
+                    "pause" | "endgame" | "gather" | "wrapup" | "meeting" | "arm" | "disarm"
+                    | "controls",
+                ..
+            }) => {
+                self.ignored(
+                    message,
+                    Ignored::Denied("You must be in the game's voice channels to do that"),
+                )
+                .await?;
+            }
+            Some(Command {
+                name: "command",
+                mut arguments,
+                ..
+            }) => self.toggle_command(message, &mut arguments).await?,
+            Some(Command {
+                name: "profile",
+                mut arguments,
+                ..
+            }) => self.switch_profile(message, &mut arguments).await?,
+            Some(Command { name: "setup", .. }) => self.setup_guild(message).await?,
+            Some(Command {
+                name: "session",
+                mut arguments,
+                ..
+            }) => self.session_command(message, &mut arguments).await?,
+            Some(Command {
+                name: "plan",
+                mut arguments,
+                ..
+            }) => self.preview_plan(message, &mut arguments).await?,
+            Some(Command { name: "optout", .. }) => self.toggle_opt_out(message).await?,
+            Some(Command { name: "tasks", .. }) => self.show_tasks(message).await?,
+            Some(Command {
+                name: "logs",
+                mut arguments,
+                ..
+            }) => self.send_logs(message, &mut arguments).await?,
+            Some(Command { name: "panic", .. }) => self.panic(message).await?,
+            Some(Command {
+                name: "history",
+                mut arguments,
+                ..
+            }) => self.show_member_history(message, &mut arguments).await?,
+            Some(Command {
+                name: "queue",
+                mut arguments,
+                ..
+            }) => self.queue_command(message, &mut arguments).await?,
+            Some(Command { name: "back", .. }) => self.unpark(message).await?,
+            Some(Command {
+                name: "teams",
+                mut arguments,
+                ..
+            }) => self.teams_command(message, &mut arguments).await?,
+            Some(Command {
+                name: "game",
+                mut arguments,
+                ..
+            }) => self.game_command(message, &mut arguments).await?,
+            Some(Command {
+                name: "buddy",
+                mut arguments,
+                ..
+            }) => self.toggle_buddy(message, &mut arguments).await?,
+            Some(Command {
+                name: "emoji",
+                mut arguments,
+                ..
+            }) => self.set_emoji(message, &mut arguments).await?,
+            Some(Command {
+                name: "normalize",
+                mut arguments,
+                ..
+            }) => self.toggle_normalize(message, &mut arguments).await?,
+            Some(Command {
+                name: "celebrate",
+                mut arguments,
+                ..
+            }) => self.set_celebration(message, &mut arguments).await?,
+            Some(Command {
+                name: "takeover",
+                mut arguments,
+                ..
+            }) => {
+                if self.owners.contains(&message.author.id) {
+                    self.take_over(message, &mut arguments).await?;
+                } else {
+                    self.ignored(message, Ignored::Denied("Only owners can take over"))
+                        .await?;
+                }
+            }
+            Some(Command { name: "stop", .. }) => {
+                if self.owners.contains(&message.author.id) {
+                    self.confirm(message, Action::Stop).await?;
+                } else {
+                    self.ignored(message, Ignored::Denied("Only owners can stop the bot"))
+                        .await?;
+                }
+            }
+            // Not ~~strikethrough~~ or a ~ on its own
+            None if message.content.strip_prefix('~').map_or(false, |rest| {
+                rest.starts_with(|c: char| c.is_ascii_alphabetic())
+            }) =>
+            {
+                self.ignored(message, Ignored::Unknown).await?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    // For commands the bot saw but won't run, so people aren't left wondering
+    async fn ignored(&self, message: &Message, ignored: Ignored) -> Result<()> {
+        let (status, hint) = match ignored {
+            Ignored::Unknown => (Status::UnknownCommand, "There is no such command"),
+            Ignored::Denied(hint) => (Status::Denied, hint),
+        };
+
+        match self.ignored_command_feedback {
+            CommandFeedback::Silent => {}
+            CommandFeedback::React => {
+                self.discord_client
+                    .create_reaction(
+                        message.channel_id,
+                        message.id,
+                        self.emoji(message.guild_id, status).reaction(),
+                    )
+                    .await?;
+            }
+            CommandFeedback::Reply => {
+                message.reply(&self.discord_client, hint)?.await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn emoji(&self, guild_id: Option<GuildId>, status: Status) -> Emoji {
+        let overrides = guild_id
+            .map(|guild_id| self.store.guild(guild_id).emoji)
+            .unwrap_or_default();
+        self.emoji.get(status, &overrides)
+    }
+
+    async fn set_emoji(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        let guild_id = match message.guild_id {
+            Some(guild_id) if self.owners.contains(&message.author.id) => guild_id,
+            _ => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Only owners can choose emoji in a server",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let reply = match (arguments.next().map(Status::from_name), arguments.next()) {
+            (None, _) => Status::ALL
+                .iter()
+                .map(|&status| format!("{} {}", self.emoji(Some(guild_id), status), status.name()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            (Some(Some(status)), Some(emoji)) => match emoji.parse::<Emoji>() {
+                Ok(emoji) => {
+                    self.store.update_guild(guild_id, |guild| {
+                        guild
+                            .emoji
+                            .insert(status.name().to_owned(), emoji.to_string())
+                    })?;
+                    format!("{} will now be shown as {}", status.name(), emoji)
+                }
+                Err(why) => format!("That {}", why),
+            },
+            (Some(Some(status)), None) => {
+                self.store
+                    .update_guild(guild_id, |guild| guild.emoji.remove(status.name()))?;
+                format!(
+                    "{} will be shown as {} again",
+                    status.name(),
+                    self.emoji(Some(guild_id), status)
+                )
+            }
+            (Some(None), _) => format!(
+                "Usage: ~emoji [<{}> [emoji]]",
+                Status::ALL
+                    .iter()
+                    .map(|status| status.name())
+                    .collect::<Vec<_>>()
+                    .join("|")
+            ),
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    // Leads the title of results embeds
+    fn win_emoji(&self, guild_id: Option<GuildId>, winner: Option<Team>) -> String {
+        match winner {
+            Some(Team::Crew) => format!("{} ", self.emoji(guild_id, Status::CrewWin)),
+            Some(Team::Impostors) => format!("{} ", self.emoji(guild_id, Status::ImpostorsWin)),
+            None => String::new(),
+        }
+    }
+
+    // The gateway doesn't always send every member with the guild, so members already in the game's
+    // voice channels at startup are fetched, rather than the first game finding the channels empty
+    async fn prime_cache(&self, guild_id: GuildId) -> Result<()> {
+        let missing = self
+            .settings()
+            .channels()
+            .into_iter()
+            .filter_map(|channel| self.cache.voice_channel_states(channel))
+            .flatten()
+            .filter(|vs| {
+                vs.guild_id == Some(guild_id)
+                    && (self.cache.member(guild_id, vs.user_id).is_none()
+                        || self.cache.user(vs.user_id).is_none())
+            })
+            .map(|vs| vs.user_id)
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        tracing::info!(
+            "Fetching {} members in voice missing from the cache",
+            missing.len()
+        );
+        for user_id in missing {
+            let member = self
+                .discord_client
+                .send("get_member", || {
+                    self.discord_client.guild_member(guild_id, user_id)
+                })
+                .await?;
+            if let Some(member) = member {
+                self.cache.update(&MemberAdd(member));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Custom emoji from another server, or since deleted, show up as plain text
+    async fn check_emoji(&self, guild_id: GuildId) -> Result<()> {
+        let mut wanted = self.emoji.custom_ids();
+        wanted.extend(
+            self.store
+                .guild(guild_id)
+                .emoji
+                .values()
+                .filter_map(|emoji| emoji.parse::<Emoji>().ok()?.custom_id()),
+        );
+        if wanted.is_empty() {
+            return Ok(());
+        }
+
+        let available = self
+            .discord_client
+            .emojis(guild_id)
+            .await?
+            .into_iter()
+            .map(|emoji| emoji.id)
+            .collect::<HashSet<_>>();
+        let missing = Status::ALL
+            .iter()
+            .map(|&status| (status, self.emoji(Some(guild_id), status)))
+            .filter(|(_, emoji)| {
+                emoji
+                    .custom_id()
+                    .map_or(false, |id| !available.contains(&id))
+            })
+            .map(|(status, emoji)| format!("{} ({})", status.name(), emoji))
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            self.notify(&format!(
+                "These emoji aren't in this server, so they will show up as text: {}",
+                missing.join(", ")
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    fn is_disabled(&self, message: &Message, name: &str) -> bool {
+        name != "setup"
+            && message.guild_id.map_or(false, |guild_id| {
+                let settings = self.store.guild(guild_id);
+                settings.disabled
+                    || (name != "command" && settings.disabled_commands.contains(name))
+            })
+    }
+
+    async fn session_command(
+        &self,
+        message: &Message,
+        arguments: &mut Arguments<'_>,
+    ) -> Result<()> {
+        if !self.owners.contains(&message.author.id) {
+            message
+                .reply(&self.discord_client, "Only owners can manage the session")?
+                .await?;
+            return Ok(());
+        }
+
+        let (action, target) = match (arguments.next(), arguments.next().map(UserId::parse)) {
+            (Some(action @ ("kick" | "unkick")), Some(Ok(target))) => (action, target),
+            _ => {
+                message
+                    .reply(&self.discord_client, "Usage: ~session <kick|unkick> @user")?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        if action == "kick" {
+            return self
+                .confirm(
+                    message,
+                    Action::Kick {
+                        guild_id: message.guild_id,
+                        target,
+                    },
+                )
+                .await;
+        }
+
+        self.session.apply(SessionEvent::PlayerUnkicked(target));
+        if let (Some(guild_id), true) = (self.in_voice(message.guild_id, target), self.kick_mute) {
+            self.discord_client
+                .update_member(
+                    MemberUpdate::new(guild_id, target, "taskinator: returned to session")
+                        .mute(false),
+                )
+                .await?;
+        }
+
+        message
+            .reply(
+                &self.discord_client,
+                format!("{} is back in the session", target.mention()),
+            )?
+            .await?;
+
+        Ok(())
+    }
+
+    // Only update members who are in voice, Discord rejects moves for anyone else
+    fn in_voice(&self, guild_id: Option<GuildId>, user_id: UserId) -> Option<GuildId> {
+        guild_id.and_then(|guild_id| self.cache.voice_state(user_id, guild_id).map(|_| guild_id))
+    }
+
+    async fn kick_player(&self, guild_id: Option<GuildId>, target: UserId) -> Result<()> {
+        self.session.apply(SessionEvent::PlayerKicked(target));
+
+        if let (Some(guild_id), Some(kick_channel)) =
+            (self.in_voice(guild_id, target), self.kick_channel)
+        {
+            let update = MemberUpdate::new(guild_id, target, "taskinator: kicked from session")
+                .mute(self.kick_mute);
+            let update = if self.mute_in_place {
+                update
+            } else {
+                update.channel_id(kick_channel)
+            };
+            self.discord_client.update_member(update).await?;
+        }
+
+        Ok(())
+    }
+
+    // Destructive commands are only carried out once whoever ran them presses confirm
+    async fn confirm(&self, message: &Message, action: Action) -> Result<()> {
+        self.request_confirmation(
+            message.channel_id,
+            Some(message.id),
+            message.author.id,
+            action,
+        )
+        .await
+    }
+
+    async fn request_confirmation(
+        &self,
+        channel_id: ChannelId,
+        reply_to: Option<MessageId>,
+        confirmer: UserId,
+        action: Action,
+    ) -> Result<()> {
+        let prompt = self
+            .discord_client
+            .create_message(channel_id)
+            .content(action.prompt())?
+            .components(confirm::buttons())?;
+        let prompt = match reply_to {
+            Some(message_id) => prompt.reply(message_id),
+            None => prompt,
+        }
+        .await?;
+
+        self.confirmations
+            .insert(prompt.id, Pending { action, confirmer });
+
+        let bot = self.clone();
+        tokio::spawn(async move {
+            sleep(confirm::TIMEOUT).await;
+
+            if bot.confirmations.remove(prompt.id).is_some() {
+                let expired = bot
+                    .discord_client
+                    .update_message(prompt.channel_id, prompt.id)
+                    .content(Some(format!("{} Timed out", action.prompt())));
+                match expired.and_then(|update| update.components(Some(Vec::new()))) {
+                    Ok(update) => {
+                        if let Err(why) = update.await {
+                            tracing::warn!("Failed to expire a confirmation: {}", why);
+                        }
+                    }
+                    Err(why) => tracing::warn!("Failed to expire a confirmation: {}", why),
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn handle_confirmation(&self, interaction: &MessageComponentInteraction) -> Result<()> {
+        let pending = match self.confirmations.get(interaction.message.id) {
+            Some(pending) => pending,
+            None => {
+                return self
+                    .respond(interaction, "This confirmation has expired", false)
+                    .await;
+            }
+        };
+
+        if interaction_user(interaction) != Some(pending.confirmer) {
+            return self
+                .respond(
+                    interaction,
+                    format!("Only {} can confirm this", pending.confirmer.mention()),
+                    true,
+                )
+                .await;
+        }
+
+        // Whichever button is pressed, the confirmation is used up
+        if self.confirmations.remove(interaction.message.id).is_none() {
+            return Ok(());
+        }
+
+        if interaction.data.custom_id != confirm::CONFIRM_ID {
+            return self.respond(interaction, "Cancelled", false).await;
+        }
+
+        // Discord only waits a few seconds for a response, so answer before doing the work
+        match pending.action {
+            Action::Stop => {
+                self.respond(interaction, "Good night", false).await?;
+                self.discord_gateway.shutdown();
+            }
+            Action::EndGame => {
+                self.respond(interaction, "Game ended, everyone has been unmuted", false)
+                    .await?;
+                self.force_end_game().await;
+            }
+            Action::Kick { guild_id, target } => {
+                self.respond(
+                    interaction,
+                    format!("{} has been removed from the session", target.mention()),
+                    false,
+                )
+                .await?;
+                self.kick_player(guild_id, target).await?;
+            }
+            Action::Swap {
+                first,
+                second,
+                keep_names,
+            } => {
+                let reply = self.swap(first, second, keep_names);
+                self.respond(interaction, reply, false).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Ephemeral responses are only shown to the member who pressed the button, anything else
+    // replaces the confirmation prompt and its buttons
+    async fn respond(
+        &self,
+        interaction: &MessageComponentInteraction,
+        content: impl Into<String>,
+        ephemeral: bool,
+    ) -> Result<()> {
+        let response = if ephemeral {
+            InteractionResponse::ChannelMessageWithSource(CallbackData {
+                allowed_mentions: None,
+                components: None,
+                content: Some(content.into()),
+                embeds: Vec::new(),
+                flags: Some(MessageFlags::EPHEMERAL),
+                tts: None,
+            })
+        } else {
+            InteractionResponse::UpdateMessage(CallbackData {
+                allowed_mentions: None,
+                components: Some(Vec::new()),
+                content: Some(content.into()),
+                embeds: Vec::new(),
+                flags: None,
+                tts: None,
+            })
+        };
+
+        self.discord_client
+            .interaction_callback(interaction.id, &interaction.token, &response)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn game_command(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        if arguments.next() != Some("history") {
+            message
+                .reply(&self.discord_client, "Usage: ~game history")?
+                .await?;
+            return Ok(());
+        }
+
+        let (listing, pages) = {
+            let history = self.store.history();
+            (history.page(0), history.pages())
+        };
+
+        if pages == 0 {
+            message
+                .reply(&self.discord_client, "No games have been played yet")?
+                .await?;
+            return Ok(());
+        }
+
+        message
+            .reply(&self.discord_client, listing)?
+            .components(history::buttons(0, pages))?
+            .await?;
+
+        Ok(())
+    }
+
+    // The control panel from ~controls, usable by anyone who could use the equivalent commands
+    async fn handle_panel(
+        &self,
+        interaction: &MessageComponentInteraction,
+        action: PanelAction,
+    ) -> Result<()> {
+        let user_id = match interaction_user(interaction) {
+            Some(user_id) if self.can_control(user_id) => user_id,
+            _ => {
+                return self
+                    .respond(
+                        interaction,
+                        "You must be in the game's voice channels to do that",
+                        true,
+                    )
+                    .await;
+            }
+        };
+
+        // Discord only waits a few seconds for a response, a meeting's mutes can take longer
+        self.discord_client
+            .interaction_callback(
+                interaction.id,
+                &interaction.token,
+                &InteractionResponse::DeferredUpdateMessage,
+            )
+            .await?;
+
+        let outcome = match action {
+            PanelAction::Meeting => self.toggle_meeting().await,
+            PanelAction::Pause => self.toggle_pause(),
+            PanelAction::EndGame => {
+                return self
+                    .request_confirmation(
+                        interaction.message.channel_id,
+                        None,
+                        user_id,
+                        Action::EndGame,
+                    )
+                    .await;
+            }
+        };
+
+        self.discord_client
+            .update_message(interaction.message.channel_id, interaction.message.id)
+            .content(Some(format!(
+                "{}\n{} ({})",
+                panel::TITLE,
+                outcome,
+                user_id.mention()
+            )))?
+            .await?;
+
+        Ok(())
+    }
+
+    async fn turn_history_page(
+        &self,
+        interaction: &MessageComponentInteraction,
+        page: usize,
+    ) -> Result<()> {
+        // Older games drop off the end, so the page may no longer exist
+        let (page, listing, pages) = {
+            let history = self.store.history();
+            let pages = history.pages();
+            let page = page.min(pages.saturating_sub(1));
+            (page, history.page(page), pages)
+        };
+
+        let response = InteractionResponse::UpdateMessage(CallbackData {
+            allowed_mentions: None,
+            components: Some(history::buttons(page, pages)),
+            content: Some(listing),
+            embeds: Vec::new(),
+            flags: None,
+            tts: None,
+        });
+
+        self.discord_client
+            .interaction_callback(interaction.id, &interaction.token, &response)
+            .await?;
+
+        Ok(())
+    }
+
+    // Dry run of a transition, nothing is sent to the members involved
+    async fn preview_plan(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        // Plans show who is dead, so keep them away from players
+        if !self.owners.contains(&message.author.id) {
+            message
+                .reply(&self.discord_client, "Only owners can preview plans")?
+                .await?;
+            return Ok(());
+        }
+
+        let plan = match arguments.next() {
+            Some("meeting-start") => self.meeting_start_plan(&self.gather_homes()),
+            // Who was ejected is only known against those dead when the meeting started
+            Some("meeting-end") if self.session.phase() != Phase::InMeeting => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "There's no meeting in progress to end",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+            Some("meeting-end") => self.mute_plan(
+                &self
+                    .current_players()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|p| p.dead && !self.session.meeting_dead().contains(&p.name))
+                    .map(|p| p.name)
+                    .collect(),
+                "meeting end",
+                "taskinator: meeting ended",
+            ),
+            Some("game-start") => {
+                self.mute_plan(&HashSet::new(), "game start", "taskinator: game started")
+            }
+            Some("voting") => self.voting_plan(),
+            Some("game-end") => Some(self.game_end_plan()),
+            _ => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Usage: ~plan <meeting-start|voting|meeting-end|game-start|game-end>",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let reply = match plan {
+            Some(plan) if plan.is_empty() => format!("The {} plan has nothing to do", plan.name()),
+            Some(plan) => format!("The {} plan would send:\n{}", plan.name(), plan),
+            None => "Must be in a lobby or game to plan".to_owned(),
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    async fn gather(&self, message: &Message) -> Result<()> {
+        let guild_id = match message.guild_id {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
+        };
+
+        if self.session.phase() != Phase::PreGame {
+            message
+                .reply(
+                    &self.discord_client,
+                    "Players can only be gathered before a game",
+                )?
+                .await?;
+            return Ok(());
+        }
+
+        if self.mute_in_place {
+            message
+                .reply(
+                    &self.discord_client,
+                    "Players can't be gathered with mute_in_place, as it moves them",
+                )?
+                .await?;
+            return Ok(());
+        }
+
+        let living_channels = self.living_channels();
+        let candidates = self
+            .cache
+            .guild_members(guild_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|&user_id| {
+                self.cache
+                    .voice_state(user_id, guild_id)
+                    .and_then(|vs| vs.channel_id)
+                    .map_or(false, |channel| !living_channels.contains(&channel))
+                    && self
+                        .cache
+                        .user(user_id)
+                        .map_or(false, |user| !self.is_excluded(&user))
+                    && !self.session.is_kicked(user_id)
+            })
+            .filter_map(|user_id| self.cache.member(guild_id, user_id))
+            .collect::<Vec<_>>();
+
+        // Only bring in members who are in the lobby or have told the bot who they are
+        let updates = self
+            .match_members_to_players(&candidates)
+            .unwrap_or_else(|| candidates.into_iter().map(|m| (m, None)).collect())
+            .into_iter()
+            .filter(|(m, p)| {
+                p.is_some()
+                    || self.session.player_name(m.user_id).is_some()
+                    || self.session.forced_name(m.user_id).is_some()
+            })
+            .map(|(m, _)| {
+                MemberUpdate::new(m.guild_id, m.user_id, "taskinator: gathered for a game")
+                    .channel_id(self.settings().living_channel)
+                    .mute(false)
+            })
+            .collect::<Vec<_>>();
+        let gathered = updates.len();
+
+        self.execute(MutePlan::new("gather").wave(updates)).await;
+
+        message
+            .reply(
+                &self.discord_client,
+                format!("Gathered {} members into the living channel", gathered),
+            )?
+            .await?;
+
+        Ok(())
+    }
+
+    async fn toggle_opt_out(&self, message: &Message) -> Result<()> {
+        let opt_out = self.store.update_user(message.author.id, |user| {
+            user.opt_out = !user.opt_out;
+            user.opt_out
+        })?;
+
+        message
+            .reply(
+                &self.discord_client,
+                if opt_out {
+                    "You will no longer be server muted, please mute yourself during rounds"
+                } else {
+                    "You will be server muted during rounds again"
+                },
+            )?
+            .await?;
+
+        Ok(())
+    }
+
+    async fn toggle_buddy(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        let reply = match (arguments.next(), message.guild_id) {
+            (None, _) => {
+                let opted_in = self.store.update_user(message.author.id, |user| {
+                    user.impostor_buddy = !user.impostor_buddy;
+                    user.impostor_buddy
+                })?;
+
+                if opted_in {
+                    "When you and another opted in player are impostors together, you will both be sent each other's Discord account"
+                } else {
+                    "Your Discord account will no longer be shared with your fellow impostors"
+                }
+            }
+            (Some(action @ ("enable" | "disable")), Some(guild_id))
+                if self.owners.contains(&message.author.id) =>
+            {
+                let enable = action == "enable";
+                self.store
+                    .update_guild(guild_id, |guild| guild.impostor_buddies = enable)?;
+
+                if enable {
+                    "Impostors who have opted in with ~buddy will be told who their fellow impostors are"
+                } else {
+                    "Impostors will no longer be told who their fellow impostors are"
+                }
+            }
+            (Some("enable" | "disable"), _) => {
+                "Only owners can enable or disable impostor buddies in a server"
+            }
+            _ => "Usage: ~buddy [enable|disable]",
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    async fn toggle_normalize(
+        &self,
+        message: &Message,
+        arguments: &mut Arguments<'_>,
+    ) -> Result<()> {
+        let reply = match (arguments.next(), message.guild_id) {
+            (Some(action @ ("enable" | "disable")), Some(guild_id))
+                if self.owners.contains(&message.author.id) =>
+            {
+                let enable = action == "enable";
+                self.store
+                    .update_guild(guild_id, |guild| guild.normalize_names = enable)?;
+
+                if enable {
+                    "Names will be matched ignoring case, full-width characters, emoji and invisible characters"
+                } else {
+                    "Names will only be matched exactly"
+                }
+            }
+            (Some("enable" | "disable"), _) => {
+                "Only owners can change how names are matched in a server"
+            }
+            _ => "Usage: ~normalize <enable|disable>",
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    async fn setup_guild(&self, message: &Message) -> Result<()> {
+        let guild_id = match message.guild_id {
+            Some(guild_id) if self.owners.contains(&message.author.id) => guild_id,
+            _ => return Ok(()),
+        };
+
+        self.store
+            .update_guild(guild_id, |settings| settings.disabled = false)?;
+
+        message
+            .reply(
+                &self.discord_client,
+                "Commands are now enabled in this server",
+            )?
+            .await?;
+
+        Ok(())
+    }
+
+    async fn toggle_command(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        if !self.owners.contains(&message.author.id) {
+            message
+                .reply(
+                    &self.discord_client,
+                    "Only owners can enable or disable commands",
+                )?
+                .await?;
+            return Ok(());
+        }
+
+        let guild_id = match message.guild_id {
+            Some(guild_id) => guild_id,
+            None => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Commands can only be toggled in a server",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let reply = match (arguments.next(), arguments.next()) {
+            (Some("enable" | "disable"), Some("command")) => {
+                "~command cannot be disabled".to_owned()
+            }
+            (Some(action @ ("enable" | "disable")), Some(command))
+                if COMMANDS.contains(&command) =>
+            {
+                let disable = action == "disable";
+                self.store.update_guild(guild_id, |settings| {
+                    if disable {
+                        settings.disabled_commands.insert(command.to_owned());
+                    } else {
+                        settings.disabled_commands.remove(command);
+                    }
+                })?;
+
+                format!(
+                    "{} ~{}",
+                    if disable { "Disabled" } else { "Enabled" },
+                    command
+                )
+            }
+            (Some("enable" | "disable"), Some(command)) => {
+                format!("There is no ~{} command", command)
+            }
+            _ => "Usage: ~command <enable|disable> <command>".to_owned(),
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    async fn switch_profile(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        let guild_id = match message.guild_id {
+            Some(guild_id) => guild_id,
+            None => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Profiles can only be used in a server",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let name = match (arguments.next(), arguments.next()) {
+            (None, _) => {
+                let active = self.store.guild(guild_id).profile;
+                let available = self.profiles.read().keys().cloned().collect::<Vec<_>>();
+                message
+                    .reply(
+                        &self.discord_client,
+                        format!(
+                            "Active profile: {}\nAvailable profiles: {}",
+                            active.as_deref().unwrap_or("none"),
+                            if available.is_empty() {
+                                "none".to_owned()
+                            } else {
+                                available.join(", ")
+                            }
+                        ),
+                    )?
+                    .await?;
+                return Ok(());
+            }
+            (Some("use"), Some(name)) => Some(name),
+            (Some("reset"), None) => None,
+            _ => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Usage: ~profile [use <profile>|reset]",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        if !self.owners.contains(&message.author.id) {
+            message
+                .reply(&self.discord_client, "Only owners can switch profiles")?
+                .await?;
+            return Ok(());
+        }
+
+        // Moving the game to other channels mid-round would strand players
+        if !matches!(self.session.phase(), Phase::PreGame | Phase::GameOver) {
+            message
+                .reply(
+                    &self.discord_client,
+                    "Profiles can't be switched during a game",
+                )?
+                .await?;
+            return Ok(());
+        }
+
+        let defaults = self.defaults.read().clone();
+        let profile = name.and_then(|name| self.profiles.read().get(name).cloned());
+        let settings = match (name, profile) {
+            (Some(_), Some(profile)) => defaults.with_profile(&profile),
+            (Some(name), None) => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        format!("There is no profile called {}", name),
+                    )?
+                    .await?;
+                return Ok(());
+            }
+            (None, _) => defaults,
+        };
+
+        for channel in settings.channels() {
+            if !is_voice_channel_in(&self.discord_client, guild_id, channel).await? {
+                message
+                    .reply(
+                        &self.discord_client,
+                        format!(
+                            "{} is not a voice channel in this server",
+                            channel.mention()
+                        ),
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        }
+
+        self.store.update_guild(guild_id, |guild| {
+            guild.profile = name.map(ToOwned::to_owned);
+        })?;
+        *self.settings.write() = settings;
+
+        message
+            .reply(
+                &self.discord_client,
+                match name {
+                    Some(name) => format!("Switched to profile {}", name),
+                    None => "Switched back to the default settings".to_owned(),
+                },
+            )?
+            .await?;
+
+        Ok(())
+    }
+
+    // Channels, delays and profiles can be changed between games, anything else needs a restart
+    pub async fn reload_config(&self, config: &Config) -> Result<()> {
+        if !matches!(self.session.phase(), Phase::PreGame | Phase::GameOver) {
+            return Err("The config can't be reloaded during a game".into());
+        }
+
+        let guild_id = self.home_guild.ok_or("The bot has no home server")?;
+
+        let defaults = Settings::from_config(config);
+        let settings = match self
+            .store
+            .guild(guild_id)
+            .profile
+            .and_then(|name| config.profiles.get(&name))
+        {
+            Some(profile) => defaults.with_profile(profile),
+            None => defaults.clone(),
+        };
+
+        for channel in settings.channels() {
+            if !is_voice_channel_in(&self.discord_client, guild_id, channel).await? {
+                return Err(
+                    format!("{} is not a voice channel in the home server", channel).into(),
+                );
+            }
+        }
+
+        *self.defaults.write() = defaults;
+        *self.profiles.write() = config.profiles.clone();
+        *self.settings.write() = settings;
+
+        tracing::info!("Reloaded the config");
+
+        Ok(())
+    }
+
+    fn can_control(&self, user_id: UserId) -> bool {
+        match self.control_permission {
+            ControlPermission::Anyone => true,
+            ControlPermission::Voice => {
+                self.owners.contains(&user_id)
+                    || self
+                        .get_living_members()
+                        .into_iter()
+                        .chain(self.get_members_in_channel(self.settings().dead_channel))
+                        .any(|m| m.user_id == user_id)
+            }
+        }
+    }
+
+    async fn arm(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        let living_channel = self.settings().living_channel;
+        let argument = arguments.collect::<Vec<_>>().join(" ");
+        let channel = match (argument.as_str(), message.guild_id) {
+            ("", _) => living_channel,
+            (argument, Some(guild_id)) => match self.find_channels(guild_id, argument).await?[..] {
+                [channel] => channel,
+                [] => {
+                    message
+                        .reply(
+                            &self.discord_client,
+                            format!(
+                                "There is no channel called {}. Usage: ~arm [#channel]",
+                                argument
+                            ),
+                        )?
+                        .await?;
+                    return Ok(());
+                }
+                _ => {
+                    message
+                        .reply(
+                            &self.discord_client,
+                            format!(
+                                "More than one channel is called {}, mention it instead",
+                                argument
+                            ),
+                        )?
+                        .await?;
+                    return Ok(());
+                }
+            },
+            (_, None) => living_channel,
+        };
+
+        let reply = match (self.home_guild, message.guild_id) {
+            (Some(home_guild), Some(guild_id)) if home_guild == guild_id => {
+                if channel == living_channel {
+                    self.store
+                        .update_guild(guild_id, |guild| guild.armed_channel = Some(channel))?;
+                    format!("Automation armed on {}", channel.mention())
+                } else {
+                    format!(
+                        "{} is not the living channel, switch to a profile that uses it first",
+                        channel.mention()
+                    )
+                }
+            }
+            _ => "The bot can only be armed in its home server".to_owned(),
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    // Channels in commands can be mentioned, or given by ID or name, e.g. "Among Us"
+    async fn find_channels(&self, guild_id: GuildId, argument: &str) -> Result<Vec<ChannelId>> {
+        if let Ok(channel) = ChannelId::parse(argument) {
+            return Ok(vec![channel]);
+        }
+        if let Ok(id) = argument.parse() {
+            return Ok(vec![ChannelId(id)]);
+        }
+
+        let name = argument.strip_prefix('#').unwrap_or(argument);
+        let channels = self
+            .discord_client
+            .send("get_guild_channels", || {
+                self.discord_client.guild_channels(guild_id)
+            })
+            .await?;
+
+        Ok(channels
+            .iter()
+            .filter(|channel| channel.name().eq_ignore_ascii_case(name))
+            .map(GuildChannel::id)
+            .collect())
+    }
+
+    async fn disarm(&self, message: &Message) -> Result<()> {
+        let reply = match (self.home_guild, message.guild_id) {
+            (Some(home_guild), Some(guild_id)) if home_guild == guild_id => {
+                self.store
+                    .update_guild(guild_id, |guild| guild.armed_channel = None)?;
+                if self.require_arming {
+                    "Automation disarmed, use ~arm to turn it back on"
+                } else {
+                    "Automation disarmed, though it is always on as require_arming is not set"
+                }
+            }
+            _ => "The bot can only be disarmed in its home server",
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    // What the bot last did to a member's voice and why, for "the bot never unmuted me" disputes
+    async fn show_member_history(
+        &self,
+        message: &Message,
+        arguments: &mut Arguments<'_>,
+    ) -> Result<()> {
+        let guild_id = match message.guild_id {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
+        };
+        let target = match arguments.next().map(UserId::parse) {
+            None => message.author.id,
+            Some(Ok(target)) => target,
+            Some(Err(_)) => {
+                message
+                    .reply(&self.discord_client, "Usage: ~history [@user]")?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let changes = self.discord_client.member_history(guild_id, target);
+        let reply = if changes.is_empty() {
+            format!(
+                "The bot hasn't changed {}'s voice since it started",
+                target.mention()
+            )
+        } else {
+            changes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "<t:{}:R> {}{}",
+                        change
+                            .at
+                            .duration_since(UNIX_EPOCH)
+                            .map_or(0, |since| since.as_secs()),
+                        change.update,
+                        if change.succeeded { "" } else { ", failed" }
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    // For when the bot misbehaves and people just want their voices back: stops the automation and
+    // undoes everything it did to members who are still in voice
+    async fn panic(&self, message: &Message) -> Result<()> {
+        if !self.owners.contains(&message.author.id) {
+            return self
+                .ignored(message, Ignored::Denied("Only owners can use ~panic"))
+                .await;
+        }
+
+        self.session.apply(SessionEvent::PauseChanged(true));
+        if let Some(guild_id) = self.home_guild {
+            self.store
+                .update_guild(guild_id, |guild| guild.armed_channel = None)?;
+        }
+
+        let living_channel = self.settings().living_channel;
+        let living_channels = self.living_channels();
+        let mut moved = 0;
+        let mut not_in_voice = 0;
+        let updates = self
+            .discord_client
+            .touched()
+            .into_iter()
+            .filter_map(|(guild_id, user_id)| {
+                // Discord can't change the voice state of members who aren't connected
+                let channel_id = self
+                    .cache
+                    .voice_state(user_id, guild_id)
+                    .and_then(|vs| vs.channel_id);
+                if channel_id.is_none() {
+                    not_in_voice += 1;
+                }
+
+                let update = MemberUpdate::new(guild_id, user_id, "taskinator: panic")
+                    .mute(false)
+                    .deaf(false);
+                let channel_id = channel_id?;
+                if self.mute_in_place || living_channels.contains(&channel_id) {
+                    Some(update)
+                } else {
+                    moved += 1;
+                    Some(update.channel_id(living_channel))
+                }
+            })
+            .collect::<Vec<_>>();
+        let total = updates.len();
+
+        // Straight to Discord, so a game over its failure budget is put right too
+        let failed = self.batch(updates).await;
+
+        message
+            .reply(
+                &self.discord_client,
+                format!(
+                    "Automation paused and disarmed. Unmuted and undeafened {} members, moving {} \
+                    back to the living channel; {} failed. {} members the bot changed aren't in \
+                    voice and may still be muted. Use ~pause to resume",
+                    total - failed,
+                    moved,
+                    failed,
+                    not_in_voice
+                ),
+            )?
+            .await?;
+
+        Ok(())
+    }
+
+    // For when the button press was missed: runs the meeting choreography without the game, and
+    // ends it again when run a second time
+    async fn toggle_meeting(&self) -> &'static str {
+        match self.session.phase() {
+            // Game updates are skipped while paused or disarmed, so the phase may be out of date
+            Phase::InGame if !self.is_active() => {
+                "The bot isn't following the game while paused or disarmed"
+            }
+            Phase::InGame
+                if !matches!(
+                    self.game_state_rx.borrow().as_ref().map(|s| &s.state),
+                    Some(State::InGame { .. })
+                ) =>
+            {
+                "There is no round in progress"
+            }
+            Phase::InGame => {
+                self.session.apply(SessionEvent::MeetingForced(true));
+                self.session
+                    .apply(SessionEvent::PhaseChanged(Phase::InMeeting));
+                if let Err(why) = self.start_meeting().await {
+                    tracing::warn!("Failed to start meeting: {}", why);
+                    self.session.apply(SessionEvent::MeetingForced(false));
+                    self.session
+                        .apply(SessionEvent::PhaseChanged(Phase::InGame));
+                    return "Couldn't match players to the game, so no meeting was called";
+                }
+                "Emergency meeting called, use ~meeting again to end it"
+            }
+            Phase::InMeeting if self.session.meeting_forced() => {
+                // Still forced while ending, so the state machine doesn't end it as well
+                tracing::info!("End meeting");
+                self.finish_meeting().await;
+                self.session.apply(SessionEvent::MeetingForced(false));
+                "Meeting ended"
+            }
+            Phase::InMeeting => "The game is already in a meeting",
+            Phase::PreGame | Phase::GameOver => "There is no game running",
+        }
+    }
+
+    fn toggle_pause(&self) -> &'static str {
+        if self.session.toggle_paused() {
+            "Automation paused"
+        } else {
+            "Automation resumed"
+        }
+    }
+
+    async fn start_meeting(&self) -> Result<()> {
+        tracing::info!("Start meeting");
+
+        let homes = self.gather_homes();
+
+        self.session.apply(SessionEvent::MeetingStarted {
+            dead: self
+                .current_players()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|p| p.dead)
+                .map(|p| p.name)
+                .collect(),
+            homes: homes.clone(),
+        });
+
+        let plan = self
+            .meeting_start_plan(&homes)
+            .ok_or("There is no game to match players against")?;
+        self.execute(plan).await;
+
+        if let Err(why) = self.take_attendance() {
+            tracing::warn!("Failed to take meeting attendance: {}", why);
+        }
+
+        Ok(())
+    }
+
+    // Living players without anyone in voice for them have missed the meeting
+    fn take_attendance(&self) -> Result<()> {
+        let present = self
+            .match_members_to_players(&self.get_living_members())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(_, p)| p)
+            .filter(|p| !p.dead)
+            .map(|p| p.name)
+            .collect::<HashSet<_>>();
+        let absent = self
+            .current_players()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| !p.dead && !present.contains(&p.name))
+            .map(|p| p.name)
+            .collect::<HashSet<_>>();
+
+        self.session.apply(SessionEvent::MeetingAttended {
+            present,
+            absent: absent.clone(),
+        });
+
+        let meetings = match (self.afk_after_meetings, self.broadcast_channel) {
+            (Some(meetings), Some(_)) => meetings,
+            _ => return Ok(()),
+        };
+
+        // Only flagged once, when they reach the limit
+        let mut afk = absent
+            .into_iter()
+            .filter(|name| self.session.missed_meetings(name) == meetings)
+            .collect::<Vec<_>>();
+        if afk.is_empty() {
+            return Ok(());
+        }
+        afk.sort();
+
+        self.announcer.send(
+            Announcement::text(format!(
+                "Possibly AFK, missed the last {} meetings: {}",
+                meetings,
+                afk.join(", ")
+            ))
+            .replacing("afk"),
+        );
+
+        Ok(())
+    }
+
+    // Everyone meets in the main living channel, remember where to send them back to
+    fn gather_homes(&self) -> HashMap<UserId, ChannelId> {
+        if self.mute_in_place {
+            return HashMap::new();
+        }
+
+        self.settings()
+            .split_channels
+            .iter()
+            .flat_map(|&channel| {
+                self.get_members_in_channel(channel)
+                    .into_iter()
+                    .map(move |m| (m.user_id, channel))
+            })
+            .collect()
+    }
+
+    fn meeting_start_plan(&self, homes: &HashMap<UserId, ChannelId>) -> Option<MutePlan> {
+        let settings = self.settings();
+        let matches = self.match_members_to_players(&self.get_living_members())?;
+
+        let mut updates = matches
+            .iter()
+            .filter_map(|(m, p)| match p {
+                Some(p) if !p.dead => {
+                    let update =
+                        MemberUpdate::new(m.guild_id, m.user_id, "taskinator: meeting started")
+                            .mute(false);
+
+                    Some(if homes.contains_key(&m.user_id) {
+                        update.channel_id(settings.living_channel)
+                    } else {
+                        update
+                    })
+                }
+                // Buried players stay muted but can follow the meeting
+                Some(_) if self.mute_in_place => {
+                    let update =
+                        MemberUpdate::new(m.guild_id, m.user_id, "taskinator: meeting started")
+                            .deaf(false);
+
+                    Some(if self.session.ghosts_unmuted() {
+                        update.mute(false)
+                    } else {
+                        update
+                    })
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        updates.extend(
+            self.get_members_in_channel(settings.dead_channel)
+                .iter()
+                .map(|m| {
+                    let update =
+                        MemberUpdate::new(m.guild_id, m.user_id, "taskinator: meeting started")
+                            .channel_id(settings.living_channel);
+
+                    if self.is_opted_out(m.user_id) || self.session.ghosts_unmuted() {
+                        update
+                    } else {
+                        update.mute(true)
+                    }
+                }),
+        );
+
+        Some(MutePlan::new("meeting start").wave(updates))
+    }
+
+    fn voting_hushed(&self) -> bool {
+        self.hush_voting
+            && matches!(
+                self.game_state_rx.borrow().as_ref().map(|s| &s.state),
+                Some(State::InGame { meeting, .. }) if voting(meeting)
+            )
+    }
+
+    // For the house rule of no talking once voting opens
+    fn voting_plan(&self) -> Option<MutePlan> {
+        let matches = self.match_members_to_players(&self.get_living_members())?;
+
+        let updates = matches
+            .iter()
+            .filter(|(m, p)| p.as_ref().map_or(false, |p| !p.dead) && !self.is_opted_out(m.user_id))
+            .map(|(m, _)| {
+                MemberUpdate::new(m.guild_id, m.user_id, "taskinator: voting started").mute(true)
+            })
+            .collect();
+
+        Some(MutePlan::new("voting").wave(updates))
+    }
+
+    async fn end_meeting(&self) {
+        tracing::info!("End meeting");
+
+        sleep(self.settings().meeting_end_delay).await;
+        self.finish_meeting().await;
+    }
+
+    // A ~meeting is ended by hand once everyone's ready, so it skips the delay, which would also
+    // hold up the event loop the command is handled on
+    async fn finish_meeting(&self) {
+        let game_over = {
+            let state = self.game_state_rx.borrow();
+
+            match state.as_ref().map(|s| &s.state) {
+                Some(State::InGame { players, .. }) => Team::from_survivors(players).is_some(),
+                _ => true,
+            }
+        };
+
+        // The win is celebrated once the game reports it has ended, however it was won
+        if game_over {
+            tracing::info!("Game is, in fact, over");
+            self.session
+                .apply(SessionEvent::PhaseChanged(Phase::GameOver));
+            self.end_game().await;
+            return;
+        }
+
+        self.session
+            .apply(SessionEvent::PhaseChanged(Phase::InGame));
+
+        // Anyone who died during the meeting was ejected
+        let ejected = {
+            let meeting_dead = self.session.meeting_dead();
+            self.current_players()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|p| p.dead && !meeting_dead.contains(&p.name))
+                .map(|p| p.name)
+                .collect::<HashSet<_>>()
+        };
+
+        if !ejected.is_empty() {
+            tracing::info!("Ejected: {:?}", ejected);
+        }
+
+        self.mute_players(&ejected, "meeting end", "taskinator: meeting ended")
+            .await;
+    }
+
+    async fn start_game(&self) {
+        tracing::info!("START GAME!");
+
+        if let Some(guild_id) = self.home_guild {
+            if let Err(why) = self.warn_unmodifiable_members(guild_id).await {
+                tracing::warn!("Failed to check the role hierarchy: {}", why);
+            }
+        }
+
+        if let Some(matches) = self.match_members_to_players(&self.get_living_members()) {
+            self.telemetry.record_matching(
+                matches.len(),
+                matches.iter().filter(|(_, p)| p.is_some()).count(),
+            );
+
+            let playing = matches
+                .iter()
+                .filter(|(_, p)| p.is_some())
+                .map(|(m, _)| m.user_id)
+                .collect::<Vec<_>>();
+            self.session
+                .apply(SessionEvent::LineupPlayed(playing.clone()));
+            self.session.apply(SessionEvent::PlayerNamesUsed(playing));
+            self.session
+                .apply(SessionEvent::PlayerNamesPruned(self.ident_ttl));
+        }
+
+        self.mute_players(&HashSet::new(), "game start", "taskinator: game started")
+            .await;
+
+        if let Err(why) = self.seat_spectators() {
+            tracing::warn!("Failed to seat spectators: {}", why);
+        }
+
+        if self.announce_unmatched {
+            if let Err(why) = self.announce_unmatched_players() {
+                tracing::warn!("Failed to announce unmatched players: {}", why);
+            }
+        }
+
+        self.reveal_impostor_buddies().await;
+
+        if self.announce_impostors {
+            if let Err(why) = self.announce_impostor_count() {
+                tracing::warn!("Failed to announce the impostor count: {}", why);
+            }
+        }
+    }
+
+    // With a full lobby, members in the living channel who aren't playing are watching this game
+    // and queued for the next, rather than unmatched players
+    fn seat_spectators(&self) -> Result<()> {
+        if self.current_players().unwrap_or_default().len() < self.lobby_size {
+            return Ok(());
+        }
+
+        // While a player has nobody in voice, an unmatched member could be them under another name
+        let members = self.get_living_members();
+        if !self
+            .players_without_members(&members)
+            .unwrap_or_default()
+            .is_empty()
+        {
+            return Ok(());
+        }
+
+        let spectators = self
+            .match_members_to_players(&members)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, p)| p.is_none())
+            .map(|(m, _)| m.user_id)
+            .collect::<Vec<_>>();
+        if spectators.is_empty() {
+            return Ok(());
+        }
+
+        self.session
+            .apply(SessionEvent::SpectatorsSeated(spectators.clone()));
+
+        if self.broadcast_channel.is_some() {
+            self.announcer.send(Announcement::text(format!(
+                "The lobby is full, so {} will spectate this game and have been queued for the \
+                next one. Use ~queue to see who is waiting",
+                spectators
+                    .iter()
+                    .map(|user_id| user_id.mention().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn queue_command(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        let reply = match arguments.next() {
+            None | Some("list") => return self.show_queue(message).await,
+            Some("join") => {
+                if self.session.queue().contains(&message.author.id) {
+                    "You're already in the queue".to_owned()
+                } else {
+                    self.session.apply(SessionEvent::Queued(message.author.id));
+                    format!("You're number {} in the queue", self.session.queue().len())
+                }
+            }
+            Some("leave") => {
+                if self.session.queue().contains(&message.author.id) {
+                    self.session
+                        .apply(SessionEvent::Dequeued(vec![message.author.id]));
+                    "You've left the queue".to_owned()
+                } else {
+                    "You weren't in the queue".to_owned()
+                }
+            }
+            Some(_) => "Usage: ~queue [join|leave|list]".to_owned(),
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    async fn teams_command(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        let reply = match (arguments.next(), arguments.next().map(UserId::parse)) {
+            (Some("shuffle"), None) => return self.shuffle_teams(message).await,
+            (Some("duo"), Some(Ok(partner))) if partner != message.author.id => {
+                self.session
+                    .apply(SessionEvent::DuoFormed(message.author.id, partner));
+                format!(
+                    "You and {} will be kept together by ~teams shuffle",
+                    partner.mention()
+                )
+            }
+            (Some("solo"), None) => {
+                self.session
+                    .apply(SessionEvent::DuoSplit(message.author.id));
+                "You'll be shuffled on your own".to_owned()
+            }
+            _ => "Usage: ~teams <shuffle|duo @user|solo>".to_owned(),
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    // Everyone in the living channels or the queue is a candidate for the next game
+    async fn shuffle_teams(&self, message: &Message) -> Result<()> {
+        let mut candidates = self
+            .get_living_members()
+            .iter()
+            .map(|m| m.user_id)
+            .collect::<Vec<_>>();
+        for user_id in self.session.queue() {
+            if !candidates.contains(&user_id) {
+                candidates.push(user_id);
+            }
+        }
+
+        if candidates.is_empty() {
+            message
+                .reply(&self.discord_client, "Nobody is waiting to play")?
+                .await?;
+            return Ok(());
+        }
+
+        let lineup = teams::shuffle(
+            &candidates,
+            &self.session.duos(),
+            &self.session.sat_out(),
+            self.lobby_size,
+        );
+        self.session
+            .apply(SessionEvent::TeamsShuffled(lineup.sitting_out.clone()));
+
+        let mentions = |user_ids: &[UserId]| {
+            user_ids
+                .iter()
+                .map(|user_id| user_id.mention().to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let mut embed = EmbedBuilder::new()
+            .title("Next game")
+            .color(0x00_80_FF)
+            .field(EmbedFieldBuilder::new("Playing", mentions(&lineup.playing)).build());
+        if !lineup.sitting_out.is_empty() {
+            embed = embed.field(
+                EmbedFieldBuilder::new("Sitting out", mentions(&lineup.sitting_out)).build(),
+            );
+        }
+
+        self.discord_client
+            .create_message(message.channel_id)
+            .embed(embed.build()?)?
+            .await?;
+
+        Ok(())
+    }
+
+    async fn show_queue(&self, message: &Message) -> Result<()> {
+        let queue = self.session.queue();
+        let reply = if queue.is_empty() {
+            "Nobody is waiting for a game".to_owned()
+        } else {
+            queue
+                .iter()
+                .enumerate()
+                .map(|(position, user_id)| format!("{}. {}", position + 1, user_id.mention()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    // Discord refuses to mute or move the server owner, or anyone whose highest role isn't below the
+    // bot's, whatever permissions the bot has
+    async fn warn_unmodifiable_members(&self, guild_id: GuildId) -> Result<()> {
+        let highest_role = |roles: &[RoleId]| {
+            roles
+                .iter()
+                .filter_map(|&role_id| self.cache.role(role_id))
+                .map(|role| role.position)
+                .max()
+                .unwrap_or_default()
+        };
+
+        let bot_position = match self.cache.member(guild_id, self.bot_id) {
+            Some(bot) => highest_role(&bot.roles),
+            None => return Ok(()),
+        };
+        let owner = self.cache.guild(guild_id).map(|guild| guild.owner_id);
+
+        let mut members = self.get_living_members();
+        members.extend(self.get_members_in_channel(self.settings().dead_channel));
+
+        let unmodifiable = members
+            .iter()
+            .filter(|m| {
+                m.guild_id == guild_id
+                    && (Some(m.user_id) == owner || highest_role(&m.roles) >= bot_position)
+            })
+            .map(|m| m.user_id.mention().to_string())
+            .collect::<Vec<_>>();
+
+        if unmodifiable.is_empty() {
+            return Ok(());
+        }
+
+        tracing::warn!("{} members can't be muted or moved", unmodifiable.len());
+        self.notify(&format!(
+            "I can't mute or move {} as they own the server or have a role at or above mine, \
+             move my role higher to fix this",
+            unmodifiable.join(", ")
+        ))
+        .await
+    }
+
+    // Only guilds that have set an image for the winning team get a celebration
+    fn celebrate(&self, winner: Team) -> Result<()> {
+        let guild_id = match (self.home_guild, self.broadcast_channel) {
+            (Some(guild_id), Some(_)) => guild_id,
+            _ => return Ok(()),
+        };
+
+        let settings = self.store.guild(guild_id);
+        let (title, image, color) = match winner {
+            Team::Crew => ("Crewmates win!", settings.crew_victory_image, 0x00_80_FF),
+            Team::Impostors => (
+                "Impostors win!",
+                settings.impostor_victory_image,
+                0xFF_00_00,
+            ),
+        };
+        let title = format!("{}{}", self.win_emoji(Some(guild_id), Some(winner)), title);
+
+        if let Some(image) = image {
+            let embed = EmbedBuilder::new()
+                .title(title)
+                .color(color)
+                .image(ImageSource::url(image)?)
+                .build()?;
+
+            self.announcer.send(Announcement::embed(embed));
+        }
+
+        Ok(())
+    }
+
+    async fn set_celebration(
+        &self,
+        message: &Message,
+        arguments: &mut Arguments<'_>,
+    ) -> Result<()> {
+        let guild_id = match message.guild_id {
+            Some(guild_id) if self.owners.contains(&message.author.id) => guild_id,
+            _ => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Only owners can set celebrations in a server",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let team = match arguments.next() {
+            Some("crew") => Team::Crew,
+            Some("impostors") => Team::Impostors,
+            _ => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Usage: ~celebrate <crew|impostors> [image url]",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let image = arguments.next().map(ToOwned::to_owned);
+        // Checked now, rather than failing to celebrate the next win
+        if image
+            .as_ref()
+            .map_or(false, |image| ImageSource::url(image).is_err())
+        {
+            message
+                .reply(
+                    &self.discord_client,
+                    "That isn't an http or https image URL",
+                )?
+                .await?;
+            return Ok(());
+        }
+
+        let reply = if image.is_some() {
+            "Wins will be celebrated with that image"
+        } else {
+            "Wins will no longer be celebrated"
+        };
+
+        self.store.update_guild(guild_id, |guild| match team {
+            Team::Crew => guild.crew_victory_image = image,
+            Team::Impostors => guild.impostor_victory_image = image,
+        })?;
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    // Only impostors who have opted in, in a guild that allows it, are revealed to each other
+    async fn reveal_impostor_buddies(&self) {
+        let buddies = self
+            .match_members_to_players(&self.get_living_members())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(m, p)| match p {
+                Some(p)
+                    if p.impostor
+                        && self.store.guild(m.guild_id).impostor_buddies
+                        && self.store.user(m.user_id).impostor_buddy =>
+                {
+                    Some((m.user_id, p.name))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if buddies.len() < 2 {
+            return;
+        }
+
+        for (user_id, _) in &buddies {
+            let others = buddies
+                .iter()
+                .filter(|(other, _)| other != user_id)
+                .map(|(other, name)| format!("{} ({})", other.mention(), name))
+                .collect::<Vec<_>>();
+
+            // One impostor's DMs failing shouldn't keep the others from theirs
+            let sent = self
+                .dm(
+                    *user_id,
+                    format!("Your fellow impostors: {}", others.join(", ")),
+                )
+                .await;
+            if let Err(why) = sent {
+                tracing::warn!("Failed to send {} their impostor buddies: {}", user_id, why);
+            }
+        }
+    }
+
+    fn reveal_impostors(&self, players: &[Player]) -> Result<()> {
+        if self.broadcast_channel.is_none() {
+            return Ok(());
+        }
+
+        let impostors = players
+            .iter()
+            .filter(|p| p.impostor)
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>();
+        if impostors.is_empty() {
+            return Ok(());
+        }
+
+        let winner = Team::from_survivors(players);
+        let (title, color) = match winner {
+            Some(Team::Crew) => ("Crewmates win!", 0x00_80_FF),
+            Some(Team::Impostors) => ("Impostors win!", 0xFF_00_00),
+            None => ("Game over", 0x80_80_80),
+        };
+        let title = format!("{}{}", self.win_emoji(self.home_guild, winner), title);
+        let embed = EmbedBuilder::new()
+            .title(title)
+            .color(color)
+            .description(format!(
+                "The impostor{} {}",
+                if impostors.len() == 1 {
+                    " was"
+                } else {
+                    "s were"
+                },
+                impostors.join(", ")
+            ))
+            .footer(EmbedFooterBuilder::new(self.game_clock()).build())
+            .build()?;
+
+        self.announcer.send(Announcement::embed(embed));
+
+        Ok(())
+    }
+
+    fn game_clock(&self) -> String {
+        let (round_time, meetings) = self.session.game_clock();
+        format!(
+            "{}m {}s in rounds, {} meeting{}",
+            round_time.as_secs() / 60,
+            round_time.as_secs() % 60,
+            meetings,
+            if meetings == 1 { "" } else { "s" }
+        )
+    }
+
+    fn announce_impostor_count(&self) -> Result<()> {
+        if self.broadcast_channel.is_none() {
+            return Ok(());
+        }
+
+        // The communicator doesn't read the game settings, but every impostor is flagged at the start
+        let players = self.current_players().unwrap_or_default();
+        let impostors = players.iter().filter(|p| p.impostor).count();
+
+        self.announcer.send(Announcement::text(format!(
+            "This game has {} impostor{} among {} players",
+            impostors,
+            if impostors == 1 { "" } else { "s" },
+            players.len()
+        )));
+
+        Ok(())
+    }
+
+    fn announce_unmatched_players(&self) -> Result<()> {
+        if self.broadcast_channel.is_none() {
+            return Ok(());
+        }
+
+        let absent_players = match self.players_without_members(&self.get_living_members()) {
+            Some(absent_players) if !absent_players.is_empty() => absent_players,
+            _ => return Ok(()),
+        };
+
+        let embed = EmbedBuilder::new()
+            .description("These players are not in the living channel and will not be muted")
+            .color(0xFF_00_00);
+
+        let embed = absent_players.iter().fold(embed, |embed, player| {
+            embed.field(EmbedFieldBuilder::new("not in voice", self.claimable_name(player)).build())
+        });
+
+        self.announcer
+            .send(Announcement::embed(embed.build()?).replacing("unmatched"));
+
+        Ok(())
+    }
+
+    fn record_session_event(&self, event: &SessionEvent) {
+        match event {
+            SessionEvent::PhaseChanged(Phase::InGame) if self.transcript.lock().is_none() => {
+                self.start_transcript();
+            }
+            SessionEvent::MeetingStarted { .. } => {
+                let matches = self
+                    .match_members_to_players(&self.get_living_members())
+                    .unwrap_or_default();
+                let players = self.current_players().unwrap_or_default();
+
+                if let Some(transcript) = self.transcript.lock().as_mut() {
+                    transcript.record_matches(&matches);
+                    transcript.record_meeting(&players);
+                }
+            }
+            SessionEvent::PhaseChanged(Phase::PreGame | Phase::GameOver) => {
+                let transcript = self.transcript.lock().take();
+                if let Some(transcript) = transcript {
+                    if let Err(why) = self.export_transcript(transcript) {
+                        tracing::warn!("Failed to export the game transcript: {}", why);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn start_transcript(&self) {
+        let players = match self.current_players() {
+            Some(players) => players,
+            None => return,
+        };
+
+        let mut transcript = Transcript::new(&players);
+        if let Some(matches) = self.match_members_to_players(&self.get_living_members()) {
+            transcript.record_matches(&matches);
+        }
+
+        *self.transcript.lock() = Some(transcript);
+    }
+
+    fn export_transcript(&self, transcript: Transcript) -> Result<()> {
+        self.store.record_game(transcript.result())?;
+
+        if self.archive_channel.is_some() {
+            self.summary
+                .lock()
+                .get_or_insert_with(SessionSummary::new)
+                .record(transcript.result());
+        }
+
+        if let Some(path) = &self.transcript_path {
+            transcript.append_to(path)?;
+        }
+
+        if self.transcript_upload && self.broadcast_channel.is_some() {
+            self.announcer.send(Announcement::file(
+                "transcript.csv",
+                transcript.to_csv(true).into_bytes(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn end_game(&self) {
+        tracing::info!("End game");
+
+        self.execute(self.game_end_plan()).await;
+
+        if let Err(why) = self.suggest_rotation() {
+            tracing::warn!("Failed to suggest the next lineup: {}", why);
+        }
+    }
+
+    // Queued members take any free places first, then the places of whoever has played the most
+    // games in a row
+    fn suggest_rotation(&self) -> Result<()> {
+        if self.broadcast_channel.is_none() {
+            return Ok(());
+        }
+
+        let queue = self.session.queue();
+        if queue.is_empty() {
+            return Ok(());
+        }
+
+        let mut lineup = self.session.streaks().into_iter().collect::<Vec<_>>();
+        lineup.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let free = self.lobby_size.saturating_sub(lineup.len());
+        let sitting_out = queue.len().saturating_sub(free).min(lineup.len());
+        let joining = queue.len().min(free + sitting_out);
+
+        let mentions = |user_ids: &[UserId]| {
+            user_ids
+                .iter()
+                .map(|user_id| user_id.mention().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut content = format!("Next game, from the queue: {}", mentions(&queue[..joining]));
+        if sitting_out > 0 {
+            let sitting_out = lineup
+                .iter()
+                .take(sitting_out)
+                .map(|(user_id, _)| *user_id)
+                .collect::<Vec<_>>();
+            content.push_str(&format!("\nSitting out: {}", mentions(&sitting_out)));
+        }
+
+        self.announcer.send(Announcement::text(content));
+
+        Ok(())
+    }
+
+    fn game_end_plan(&self) -> MutePlan {
+        let settings = self.settings();
+        let mut updates = self
+            .get_living_members()
+            .iter()
+            .map(|m| {
+                let update =
+                    MemberUpdate::new(m.guild_id, m.user_id, "taskinator: game ended").mute(false);
+
+                if self.mute_in_place {
+                    update.deaf(false)
+                } else {
+                    update
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Nobody was moved out with mute_in_place, so there's nobody to bring back
+        if self.mute_in_place {
+            return MutePlan::new("game end").wave(updates);
+        }
+
+        updates.extend(
+            self.get_members_in_channel(settings.dead_channel)
+                .iter()
+                .map(|m| {
+                    MemberUpdate::new(m.guild_id, m.user_id, "taskinator: game ended")
+                        .channel_id(settings.living_channel)
+                }),
+        );
+
+        // Spectators stay in the lounge, only players promoted there are brought back
+        if let Some(lounge) = settings.lounge_channel {
+            updates.extend(
+                self.match_members_to_players(&self.get_members_in_channel(lounge))
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|(_, p)| p.is_some())
+                    .map(|(m, _)| {
+                        MemberUpdate::new(m.guild_id, m.user_id, "taskinator: game ended")
+                            .channel_id(settings.living_channel)
+                    }),
+            );
+        }
+
+        MutePlan::new("game end").wave(updates)
+    }
+
+    // The dead are moved out of the living channel, or with mute_in_place kept where they are but
+    // unable to hear or be heard by the living
+    fn bury(
+        &self,
+        update: MemberUpdate,
+        user_id: UserId,
+        ghost_channel: ChannelId,
+    ) -> MemberUpdate {
+        if self.mute_in_place {
+            update.mute(!self.is_opted_out(user_id)).deaf(true)
+        } else {
+            update.channel_id(ghost_channel).mute(false)
+        }
+    }
+
+    // Players who have been dead for long enough are sent to the lounge rather than the dead channel
+    fn ghost_channel(&self, name: &str) -> ChannelId {
+        let settings = self.settings();
+
+        match (settings.lounge_channel, self.lounge_after_meetings) {
+            (Some(lounge), Some(meetings)) if self.session.dead_meetings(name) >= meetings => {
+                lounge
+            }
+            _ => settings.dead_channel,
+        }
+    }
+
+    async fn mute_players(
+        &self,
+        ejected: &HashSet<String>,
+        name: &'static str,
+        reason: &'static str,
+    ) {
+        // The game may have gone since a ~meeting was called
+        let plan = match self.mute_plan(ejected, name, reason) {
+            Some(plan) => plan,
+            None => {
+                tracing::warn!(
+                    "Skipping plan {}, there is no game to match players against",
+                    name
+                );
+                return;
+            }
+        };
+
+        let started = Instant::now();
+        self.execute(plan).await;
+        self.telemetry.record_mute_wave(started.elapsed());
+
+        self.remind_opted_out().await;
+    }
+
+    fn mute_plan(
+        &self,
+        ejected: &HashSet<String>,
+        name: &'static str,
+        reason: &'static str,
+    ) -> Option<MutePlan> {
+        let dead_channel = self.settings().dead_channel;
+        let mute = |(m, p): &(CachedMember, Option<Player>)| match p {
+            // Unconfirmed deaths stay muted with the living until a later poll agrees
+            Some(p)
+                if p.dead
+                    && (ejected.contains(&p.name) || self.session.death_confirmed(&p.name)) =>
+            {
+                Some(self.bury(
+                    MemberUpdate::new(
+                        m.guild_id,
+                        m.user_id,
+                        if ejected.contains(&p.name) {
+                            "taskinator: player ejected"
+                        } else {
+                            "taskinator: player died"
+                        },
+                    ),
+                    m.user_id,
+                    self.ghost_channel(&p.name),
+                ))
+            }
+            Some(_) => {
+                let home = self.session.home_channel(m.user_id);
+
+                if self.is_opted_out(m.user_id) {
+                    return home.map(|home| {
+                        MemberUpdate::new(m.guild_id, m.user_id, reason).channel_id(home)
+                    });
+                }
+
+                let update = MemberUpdate::new(m.guild_id, m.user_id, reason).mute(true);
+
+                // Send players gathered from a split channel back there
+                Some(match home {
+                    Some(home) => update.channel_id(home),
+                    None => update,
+                })
+            }
+            // Treat players who dropped from the game as dead in case they reconnect
+            None if self.is_reconnecting(m.user_id) => Some(self.bury(
+                MemberUpdate::new(m.guild_id, m.user_id, "taskinator: player disconnected"),
+                m.user_id,
+                dead_channel,
+            )),
+            _ => None,
+        };
+
+        // Move ejected players out before muting everyone else, so their last words don't leak
+        // into the living channel
+        let (ejected_matches, matches) = self
+            .match_members_to_players(&self.get_living_members())?
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, p)| {
+                p.as_ref().map_or(false, |p| ejected.contains(&p.name))
+            });
+
+        Some(
+            MutePlan::new(name)
+                .wave(ejected_matches.iter().filter_map(&mute).collect())
+                .wave(matches.iter().filter_map(&mute).collect()),
+        )
+    }
+
+    // Works from who should be muted right now rather than from what changed, so it still puts
+    // things right after missed transitions. Only members whose voice state differs are updated
+    fn reconcile_plan(&self) -> Option<MutePlan> {
+        const REASON: &str = "taskinator: reconciling voice states";
+
+        let is_muted = |m: &CachedMember| {
+            self.cache
+                .voice_state(m.user_id, m.guild_id)
+                .map_or(false, |vs| vs.mute)
+        };
+
+        let is_deafened = |m: &CachedMember| {
+            self.cache
+                .voice_state(m.user_id, m.guild_id)
+                .map_or(false, |vs| vs.deaf)
+        };
+        // Only buried players are deafened, and only while a round is being played
+        let undeafen = |m: &CachedMember| self.mute_in_place && is_deafened(m);
+
+        let updates = match self.session.phase() {
+            Phase::PreGame | Phase::GameOver => self
+                .get_living_members()
+                .iter()
+                .filter(|m| is_muted(m) || undeafen(m))
+                .map(|m| {
+                    let update = MemberUpdate::new(m.guild_id, m.user_id, REASON).mute(false);
+                    if undeafen(m) {
+                        update.deaf(false)
+                    } else {
+                        update
+                    }
+                })
+                .collect(),
+            phase => self
+                .match_members_to_players(&self.get_living_members())?
+                .iter()
+                .filter_map(|(m, p)| {
+                    let p = p.as_ref()?;
+                    let update = MemberUpdate::new(m.guild_id, m.user_id, REASON);
+                    let should_mute = if phase == Phase::InGame {
+                        if p.dead && self.session.death_confirmed(&p.name) {
+                            if self.mute_in_place && is_deafened(m) {
+                                return None;
+                            }
+                            return Some(self.bury(update, m.user_id, self.ghost_channel(&p.name)));
+                        }
+                        !self.is_opted_out(m.user_id)
+                    } else {
+                        ((p.dead && !self.session.ghosts_unmuted()) || self.voting_hushed())
+                            && !self.is_opted_out(m.user_id)
+                    };
+
+                    match (should_mute == is_muted(m), undeafen(m)) {
+                        (true, false) => None,
+                        (true, true) => Some(update.deaf(false)),
+                        (false, false) => Some(update.mute(should_mute)),
+                        (false, true) => Some(update.mute(should_mute).deaf(false)),
+                    }
+                })
+                .collect(),
+        };
+
+        Some(MutePlan::new("reconcile").wave(updates))
+    }
+
+    // Polled during an outage: once Discord answers again, the outage is over
+    async fn check_outage_over(&self) {
+        let guild_id = match self.home_guild {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+
+        let probe = self
+            .discord_client
+            .send("get_guild", || self.discord_client.guild(guild_id))
+            .await;
+        if let Ok(Some(_)) = probe {
+            self.end_outage().await;
+        }
+    }
+
+    async fn end_outage(&self) {
+        let since = match self.session.outage_since() {
+            Some(since) => since,
+            None => return,
+        };
+        self.session.apply(SessionEvent::OutageEnded);
+        tracing::info!("Discord is available again after {:?}", since.elapsed());
+
+        if self.is_active() {
+            if let Some(plan) = self.reconcile_plan() {
+                self.execute(plan).await;
+            }
+        }
+
+        let _e_msg = self
+            .notify(&format!(
+                "Discord was unavailable for {} seconds, so the bot held off muting and moving \
+                players. Everyone's voice has now been brought in line with the game",
+                since.elapsed().as_secs()
+            ))
+            .await;
+    }
+
+    // Once a game has used up its failure budget nothing more is attempted until it ends, rather
+    // than failing the same way at every transition
+    fn is_degraded(&self) -> bool {
+        self.session.failed_updates() >= self.failure_budget
+    }
+
+    async fn execute(&self, plan: MutePlan) {
+        let name = plan.name();
+        let total = plan.len();
+
+        if self.is_degraded() {
+            tracing::warn!("Skipping plan {}, too many updates failed this game", name);
+            return;
+        }
+
+        // The game carries on regardless, the voice states are reconciled with it once Discord is back
+        if self.session.outage_since().is_some() {
+            tracing::warn!("Skipping plan {}, Discord is unavailable", name);
+            return;
+        }
+
+        tracing::debug!("Executing plan {}:\n{}", name, plan);
+
+        let mut failed = 0;
+        let mut sent = Vec::with_capacity(total);
+        for wave in plan.into_waves() {
+            sent.extend_from_slice(&wave);
+            failed += self.batch(wave).await;
+        }
+
+        // Discord accepting every request doesn't mean they all stuck, e.g. a member rejoining
+        // voice mid-plan comes back with their old state
+        if failed == 0 {
+            failed = self.verify(&sent).await;
+        }
+
+        if failed == 0 {
+            tracing::info!("Plan {} applied {} updates", name, total);
+            return;
+        }
+
+        tracing::warn!("Plan {} failed {} of {} updates", name, failed, total);
+
+        // Discord's own failures aren't counted against the failure budget
+        if self.discord_client.discord_down() {
+            tracing::warn!("Discord is failing requests, holding off until it recovers");
+            self.session
+                .apply(SessionEvent::OutageStarted(Instant::now()));
+            return;
+        }
+
+        self.session.apply(SessionEvent::UpdatesFailed(
+            u32::try_from(failed).unwrap_or(u32::MAX),
+        ));
+
+        if self.is_degraded() {
+            let guild = self
+                .home_guild
+                .map_or_else(String::new, |id| id.to_string());
+            reporting::report(
+                "Too many member updates failed, stopped automating the game",
+                &[("guild", guild), ("phase", self.phase().to_owned())],
+            );
+            let _e_msg = self
+                .notify(&format!(
+                    "{} member updates have failed this game, so the bot will stop muting and \
+                    moving players until it ends. Check its permissions and role position, or use \
+                    ~endgame to start afresh",
+                    self.session.failed_updates()
+                ))
+                .await;
+        }
+    }
+
+    // Members whose voice state doesn't show their update are sent it once more, returns how many
+    // still don't after that
+    async fn verify(&self, updates: &[MemberUpdate]) -> usize {
+        // Voice states reach the cache over the gateway, a little after the requests return
+        const SETTLE: Duration = Duration::from_secs(2);
+
+        let unsettled = |updates: &[MemberUpdate]| {
+            updates
+                .iter()
+                .filter(|update| {
+                    let (guild_id, user_id) = update.member();
+                    // Anyone who has left voice since can't be updated, so isn't waited on
+                    self.cache
+                        .voice_state(user_id, guild_id)
+                        .map_or(false, |vs| !update.settled(vs.mute, vs.deaf, vs.channel_id))
+                })
+                .copied()
+                .collect::<Vec<_>>()
+        };
+
+        sleep(SETTLE).await;
+        let retry = unsettled(updates);
+        if retry.is_empty() {
+            return 0;
+        }
+
+        tracing::warn!("{} updates didn't stick, retrying them", retry.len());
+        if self.batch(retry.clone()).await > 0 {
+            return retry.len();
+        }
+
+        sleep(SETTLE).await;
+        let still = unsettled(&retry);
+        for update in &still {
+            tracing::warn!("Update didn't stick: {}", update);
+        }
+
+        still.len()
+    }
+
+    fn track_departures(&self) {
+        let matches = match self.match_members_to_players(&self.get_living_members()) {
+            Some(matches) => matches,
+            None => return,
+        };
+
+        for (member, player) in &matches {
+            if player.is_some() {
+                self.session.apply(SessionEvent::PlayerSeen(member.user_id));
+            } else if self.session.is_seen(member.user_id) {
+                tracing::info!("{} dropped from the game", member.user_id);
+                self.session.apply(SessionEvent::PlayerDeparted {
+                    user_id: member.user_id,
+                    at: Instant::now(),
+                });
+            }
+        }
+    }
+
+    // Members still in voice long after dropping from the game are parked in the AFK channel, so
+    // the living channel only holds the lobby
+    async fn park_departed(&self) -> Result<()> {
+        let afk_channel = match self.afk_channel {
+            Some(afk_channel) => afk_channel,
+            None => return Ok(()),
+        };
+
+        let departed = self
+            .get_living_members()
+            .into_iter()
+            .filter(|m| {
+                self.session
+                    .departed_at(m.user_id)
+                    .map_or(false, |departed| {
+                        departed.elapsed() >= self.afk_channel_after
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        for member in departed {
+            tracing::info!("Parking {} as AFK", member.user_id);
+            self.session
+                .apply(SessionEvent::PlayerParked(member.user_id));
+            let update = MemberUpdate::new(
+                member.guild_id,
+                member.user_id,
+                "taskinator: AFK, use ~back",
+            )
+            .mute(false);
+            // Without Move Members they stay in the living channel, just out of the automation
+            let update = if self.mute_in_place {
+                update.deaf(false)
+            } else {
+                update.channel_id(afk_channel)
+            };
+            self.discord_client.update_member(update).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn unpark(&self, message: &Message) -> Result<()> {
+        let user_id = message.author.id;
+        if !self.session.is_parked(user_id) {
+            message
+                .reply(&self.discord_client, "You weren't marked as AFK")?
+                .await?;
+            return Ok(());
+        }
+
+        self.session.apply(SessionEvent::PlayerUnparked(user_id));
+        if let (Some(guild_id), Some(afk_channel), false) = (
+            self.in_voice(message.guild_id, user_id),
+            self.afk_channel,
+            self.mute_in_place,
+        ) {
+            let in_afk_channel = self
+                .cache
+                .voice_state(user_id, guild_id)
+                .map_or(false, |vs| vs.channel_id == Some(afk_channel));
+            if in_afk_channel {
+                self.discord_client
+                    .update_member(
+                        MemberUpdate::new(guild_id, user_id, "taskinator: back from AFK")
+                            .channel_id(self.settings().living_channel),
+                    )
+                    .await?;
+            }
+        }
+
+        message.reply(&self.discord_client, "Welcome back")?.await?;
+
+        Ok(())
+    }
+
+    fn is_reconnecting(&self, user_id: UserId) -> bool {
+        self.session.departed_at(user_id).map_or(false, |departed| {
+            departed.elapsed() < self.settings().reconnect_grace
+        })
+    }
+
+    async fn ident_player(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        match arguments.next() {
+            Some("prune") => self.prune_idents(message, arguments).await?,
+            Some(argument) => {
+                if let Ok(target) = UserId::parse(argument) {
+                    if self.owners.contains(&message.author.id) {
+                        if let Some(ign) = arguments.next() {
+                            self.session.apply(SessionEvent::PlayerNamed {
+                                user_id: target,
+                                name: ign.to_owned(),
+                            });
+                            message
+                                .reply(
+                                    &self.discord_client,
+                                    format!("Set {}'s IGN to {}", target.mention(), ign),
+                                )?
+                                .await?;
+                        } else {
+                            message
+                                .reply(&self.discord_client, "You must include an in game name")?
+                                .await?;
+                        }
+                    } else {
+                        message
+                            .reply(
+                                &self.discord_client,
+                                "Only owners can set the in game name of another user",
+                            )?
+                            .await?;
+                    }
+                } else {
+                    self.session.apply(SessionEvent::PlayerNamed {
+                        user_id: message.author.id,
+                        name: argument.to_owned(),
+                    });
+                    message
+                        .reply(
+                            &self.discord_client,
+                            format!("Set your in game name to {}", argument),
+                        )?
+                        .await?;
+                }
+            }
+            None => {
+                message
+                    .reply(&self.discord_client, "Please include your in game name")?
+                    .await?;
+            }
+        };
+
+        Ok(())
+    }
+
+    async fn prune_idents(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        if !self.owners.contains(&message.author.id) {
+            message
+                .reply(&self.discord_client, "Only owners can prune in game names")?
+                .await?;
+            return Ok(());
+        }
+
+        let max_age = match arguments.next().map(str::parse::<u64>) {
+            None => self.ident_ttl,
+            Some(Ok(days)) => Duration::from_secs(days.saturating_mul(DAY_SECS)),
+            Some(Err(_)) => {
+                message
+                    .reply(&self.discord_client, "Usage: ~ident prune [days]")?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let before = self.session.player_name_count();
+        self.session.apply(SessionEvent::PlayerNamesPruned(max_age));
+        let pruned = before - self.session.player_name_count();
+
+        message
+            .reply(
+                &self.discord_client,
+                format!(
+                    "Removed {} in game names not used in the last {} days",
+                    pruned,
+                    max_age.as_secs() / DAY_SECS
+                ),
+            )?
+            .await?;
+
+        Ok(())
+    }
+
+    // Unmatched players are listed with a code, so members can claim names they can't type
+    fn claimable_name(&self, player: &Player) -> String {
+        self.current_players()
+            .and_then(|players| players.iter().position(|p| p.name == player.name))
+            .map_or_else(
+                || player.name.clone(),
+                |index| format!("{} (~claim {})", player.name, short_code(index)),
+            )
+    }
+
+    async fn claim_player(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        let player = match arguments.next().and_then(code_index) {
+            Some(index) => self
+                .current_players()
+                .and_then(|players| players.into_iter().nth(index)),
+            None => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Usage: ~claim <CODE>, ~check shows the codes of unmatched players",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let player = match player {
+            Some(player) => player,
+            None => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "There is no player with that code, use ~check to see the current codes",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let forced_elsewhere = self
+            .session
+            .forced_names()
+            .into_iter()
+            .any(|(user_id, name)| user_id != message.author.id && name == player.name);
+        let unmatched = self
+            .players_without_members(&self.get_living_members())
+            .unwrap_or_default()
+            .iter()
+            .any(|p| p.name == player.name);
+
+        let reply = if forced_elsewhere || !unmatched {
+            format!("{} is already matched to someone", player.name)
+        } else {
+            self.session.apply(SessionEvent::PlayerForced {
+                user_id: message.author.id,
+                name: Some(player.name.clone()),
+            });
+            format!("You are now matched to {} for this session", player.name)
+        };
+
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    // For two members who identified as each other, fixed without two careful ~forcematches
+    async fn swap_players(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        let (first, second) = match (
+            arguments.next().map(UserId::parse),
+            arguments.next().map(UserId::parse),
+        ) {
+            (Some(Ok(first)), Some(Ok(second))) if first != second => (first, second),
+            _ => {
+                message
+                    .reply(&self.discord_client, "Usage: ~swap @user @user [keep]")?
+                    .await?;
+                return Ok(());
+            }
+        };
+        let keep_names = arguments.next() == Some("keep");
+
+        if !self.owners.contains(&message.author.id) {
+            // Members can only swap with someone who agrees to it
+            let other = match message.author.id {
+                id if id == first => second,
+                id if id == second => first,
+                _ => {
+                    return self
+                        .ignored(
+                            message,
+                            Ignored::Denied("Only owners can swap other members' players"),
+                        )
+                        .await;
+                }
+            };
+
+            return self
+                .request_confirmation(
+                    message.channel_id,
+                    Some(message.id),
+                    other,
+                    Action::Swap {
+                        first,
+                        second,
+                        keep_names,
+                    },
+                )
+                .await;
+        }
+
+        let reply = self.swap(first, second, keep_names);
+        message.reply(&self.discord_client, reply)?.await?;
+
+        Ok(())
+    }
+
+    fn swap(&self, first: UserId, second: UserId, keep_names: bool) -> String {
+        let mut members = self.get_living_members();
+        members.extend(self.get_members_in_channel(self.settings().dead_channel));
+        let matches = self.match_members_to_players(&members).unwrap_or_default();
+        let player = |user_id: UserId| {
+            matches
+                .iter()
+                .find(|(m, _)| m.user_id == user_id)
+                .and_then(|(_, p)| p.as_ref())
+                .map(|p| p.name.clone())
+        };
+
+        match (player(first), player(second)) {
+            (Some(first_name), Some(second_name)) => {
+                self.session.apply(SessionEvent::PlayersSwapped {
+                    first: (first, first_name.clone()),
+                    second: (second, second_name.clone()),
+                    keep_names,
+                });
+                format!(
+                    "{} is now {} and {} is now {} for this session{}",
+                    first.mention(),
+                    second_name,
+                    second.mention(),
+                    first_name,
+                    if keep_names {
+                        ", and their ~ident names were swapped"
+                    } else {
+                        ""
+                    }
+                )
+            }
+            (None, _) => format!("{} isn't matched to a player", first.mention()),
+            (_, None) => format!("{} isn't matched to a player", second.mention()),
+        }
+    }
+
+    async fn force_match(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        if !self.owners.contains(&message.author.id) {
+            message
+                .reply(&self.discord_client, "Only owners can force a match")?
+                .await?;
+            return Ok(());
+        }
+
+        let target = match arguments.next().map(UserId::parse) {
+            Some(Ok(target)) => target,
+            _ => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Usage: ~forcematch @user [in game name]",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let name = arguments.collect::<Vec<_>>().join(" ");
+        if name.is_empty() {
+            self.session.apply(SessionEvent::PlayerForced {
+                user_id: target,
+                name: None,
+            });
+            message
+                .reply(
+                    &self.discord_client,
+                    format!("Cleared the forced match for {}", target.mention()),
+                )?
+                .await?;
+            return Ok(());
+        }
+
+        // Names with invisible characters can't be typed exactly, so fall back to a loose match
+        let player = self.current_players().and_then(|players| {
+            players
+                .iter()
+                .find(|p| p.name == name)
+                .or_else(|| {
+                    players
+                        .iter()
+                        .find(|p| visible_name(&p.name) == visible_name(&name))
+                })
+                .map(|p| p.name.clone())
+        });
+
+        match player {
+            Some(player) => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        format!("Forced {} to match {}", target.mention(), player),
+                    )?
+                    .await?;
+                self.session.apply(SessionEvent::PlayerForced {
+                    user_id: target,
+                    name: Some(player),
+                });
+            }
+            None => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        format!("There is no player called {} in the game", name),
+                    )?
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn check_matching(&self, message: &Message) -> Result<()> {
+        let members = self.get_living_members();
+
+        match self.match_members(&members) {
+            Some(matched_players) => {
+                tracing::trace!("{:?}", matched_players);
+                let matched = matched_players
+                    .iter()
+                    .filter_map(|(m, matched)| {
+                        let (p, strategy) = matched.as_ref()?;
+                        Some(format!(
+                            "{} as {} ({})",
+                            m.user_id.mention(),
+                            p.name,
+                            strategy
+                        ))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let unmatched_players = matched_players
+                    .into_iter()
                     .filter_map(|(m, p)| if p.is_none() { Some(m.user_id) } else { None })
                     .collect::<Vec<_>>();
+                let absent_players = self.players_without_members(&members).unwrap_or_default();
 
-                if unmatched_players.is_empty() {
+                if unmatched_players.is_empty() && absent_players.is_empty() {
                     self.discord_client
                         .create_message(message.channel_id)
-                        .content("All members matched to player")?
+                        .content(format!("All members matched to players:\n{}", matched))?
                         .reply(message.id)
                         .await?;
                 } else {
                     let embed = EmbedBuilder::new()
-                        .description("Could not match all members to players")
+                        .description(format!(
+                            "Could not match all members to players\n\n{}",
+                            matched
+                        ))
                         .color(0xFF_00_00);
 
                     let embed = unmatched_players.iter().fold(embed, |embed, uid| {
@@ -518,6 +4990,13 @@ impl Bot {
                         )
                     });
 
+                    let embed = absent_players.iter().fold(embed, |embed, player| {
+                        embed.field(
+                            EmbedFieldBuilder::new("not in voice", self.claimable_name(player))
+                                .build(),
+                        )
+                    });
+
                     self.discord_client
                         .create_message(message.channel_id)
                         .embed(embed.build()?)?
@@ -536,84 +5015,588 @@ impl Bot {
         Ok(())
     }
 
+    // Everything needed to look into a bug report, with secrets and IDs taken out so it can be
+    // attached to a public issue
+    async fn send_diag_bundle(&self, message: &Message) -> Result<()> {
+        if !self.owners.contains(&message.author.id) {
+            return self
+                .ignored(
+                    message,
+                    Ignored::Denied("Only owners can get a diagnostics bundle"),
+                )
+                .await;
+        }
+
+        let mut sanitizer = Sanitizer::default();
+        let config = std::fs::read_to_string(CONFIG_PATH)
+            .map_err(Into::into)
+            .and_then(|config| sanitizer.config(&config))
+            .unwrap_or_else(|why| format!("Failed to read the config: {}", why));
+        let logs = self
+            .logs
+            .tail(Level::TRACE, Duration::from_secs(u64::MAX))
+            .join("\n");
+        let states = self.states.lock().dump();
+
+        let bundle = diag::bundle(&[
+            ("version.txt", diag::version()),
+            ("config.toml", config),
+            ("logs.txt", sanitizer.text(&logs)),
+            ("states.txt", sanitizer.text(&states)),
+        ])?;
+
+        let sent = self
+            .dm_attachment(
+                message.author.id,
+                "Diagnostics bundle, with the token and Discord IDs taken out".to_owned(),
+                Some(("taskinator-diag.zip", bundle)),
+            )
+            .await?;
+        if !sent {
+            self.dms_closed(message).await?;
+        }
+
+        Ok(())
+    }
+
+    // Recent log records by DM, for owners who can't get at the host
+    async fn send_logs(&self, message: &Message, arguments: &mut Arguments<'_>) -> Result<()> {
+        // Leaves room for the code block around each message
+        const MAX_LENGTH: usize = 1900;
+
+        if !self.owners.contains(&message.author.id) {
+            return self
+                .ignored(message, Ignored::Denied("Only owners can read the logs"))
+                .await;
+        }
+
+        let parsed = match arguments.next() {
+            Some("tail") => {
+                let level = arguments
+                    .next()
+                    .map_or(Ok(Level::INFO), str::parse::<Level>);
+                let window = arguments
+                    .next()
+                    .map_or(Some(Duration::from_secs(10 * 60)), parse_minutes);
+                level.ok().zip(window)
+            }
+            _ => None,
+        };
+        let (level, window) = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                message
+                    .reply(
+                        &self.discord_client,
+                        "Usage: ~logs tail [error|warn|info|debug] [minutes]",
+                    )?
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let records = self.logs.tail(level, window);
+
+        let mut chunks = Vec::new();
+        if records.is_empty() {
+            chunks.push(format!(
+                "Nothing was logged in the last {} minutes",
+                window.as_secs() / 60
+            ));
+        } else {
+            let mut chunk = String::new();
+            for record in records {
+                let record = record.chars().take(MAX_LENGTH).collect::<String>();
+                if chunk.len() + record.len() + 1 > MAX_LENGTH {
+                    chunks.push(format!("```\n{}```", chunk));
+                    chunk.clear();
+                }
+                chunk.push_str(&record);
+                chunk.push('\n');
+            }
+            chunks.push(format!("```\n{}```", chunk));
+        }
+
+        for chunk in chunks {
+            if !self.dm(message.author.id, chunk).await? {
+                return self.dms_closed(message).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn show_tasks(&self, message: &Message) -> Result<()> {
+        if !self.owners.contains(&message.author.id) {
+            return self
+                .ignored(
+                    message,
+                    Ignored::Denied("Only owners can see the bot's tasks"),
+                )
+                .await;
+        }
+
+        let embed = self.supervisor.tasks().into_iter().fold(
+            EmbedBuilder::new().title("Tasks").color(0x00_80_FF),
+            |embed, (task, info)| {
+                let mut value = format!("{}, restarted {} times", info.status, info.restarts);
+                if let Some(last_error) = info.last_error {
+                    value.push_str(&format!("\nLast error: {}", last_error));
+                }
+                embed.field(EmbedFieldBuilder::new(task, value).build())
+            },
+        );
+
+        self.discord_client
+            .create_message(message.channel_id)
+            .embed(embed.build()?)?
+            .await?;
+
+        Ok(())
+    }
+
+    async fn show_status(&self, message: &Message) -> Result<()> {
+        let embed = self.status_embed(message.guild_id);
+
+        self.discord_client
+            .create_message(message.channel_id)
+            .embed(embed.build()?)?
+            .await?;
+
+        Ok(())
+    }
+
+    fn status_embed(&self, guild_id: Option<GuildId>) -> EmbedBuilder {
+        let stats = self.discord_client.stats();
+        let snapshot = self.telemetry.snapshot();
+        let embed = EmbedBuilder::new()
+            .description(format!(
+                "Phase: {}{}\nDiscord API: {} requests, {} retried, {} failed\n\
+                 Matched {} of members over {} games, mute waves take {}",
+                self.phase(),
+                if self.is_paused() {
+                    " (paused)"
+                } else if self.locked_out.lock().is_some() {
+                    " (another instance holds the server)"
+                } else if self.session.outage_since().is_some() {
+                    " (waiting for Discord to recover)"
+                } else if self.is_degraded() {
+                    " (degraded, too many failed updates)"
+                } else {
+                    ""
+                },
+                stats.requests,
+                stats.retries,
+                stats.failures,
+                snapshot
+                    .match_rate()
+                    .map_or_else(|| "n/a".to_owned(), |rate| format!("{:.0}%", rate * 100.0)),
+                snapshot.games,
+                snapshot.average_mute_wave().map_or_else(
+                    || "n/a".to_owned(),
+                    |wave| format!("{}ms", wave.as_millis())
+                ),
+            ))
+            .color(0x00_80_FF);
+
+        let embed = if matches!(self.session.phase(), Phase::InGame | Phase::InMeeting) {
+            embed.footer(EmbedFooterBuilder::new(self.game_clock()).build())
+        } else {
+            embed
+        };
+
+        // They won't get reminders or impostor buddies by DM
+        let dms_closed = self
+            .settings()
+            .channels()
+            .into_iter()
+            .flat_map(|channel| self.get_members_in_channel(channel))
+            .filter(|m| self.store.user(m.user_id).dms_closed)
+            .map(|m| m.user_id.mention().to_string())
+            .collect::<Vec<_>>();
+        let embed = if dms_closed.is_empty() {
+            embed
+        } else {
+            embed.field(EmbedFieldBuilder::new("DMs closed", dms_closed.join(", ")).build())
+        };
+
+        self.roster().into_iter().fold(embed, |embed, entry| {
+            embed.field(
+                EmbedFieldBuilder::new(
+                    entry.name,
+                    format!(
+                        "{} {}",
+                        self.emoji(
+                            guild_id,
+                            if entry.dead {
+                                Status::Dead
+                            } else {
+                                Status::Alive
+                            }
+                        ),
+                        entry
+                            .user_id
+                            .map_or_else(|| "unmatched".to_owned(), |id| id.mention().to_string())
+                    ),
+                )
+                .inline()
+                .build(),
+            )
+        })
+    }
+
+    async fn show_diagnostics(&self, message: &Message) -> Result<()> {
+        let stats = self.discord_client.stats();
+        let snapshot = self.telemetry.snapshot();
+        let millis = |millis: Option<u64>| {
+            millis.map_or_else(|| "n/a".to_owned(), |millis| format!("{}ms", millis))
+        };
+
+        let embed = EmbedBuilder::new()
+            .title("Diagnostics")
+            .description(format!(
+                "Transitions handled: {}, recent latency p50 {}, p95 {}\n\
+                 Mute waves take {} on average\n\
+                 Discord API: {} requests, {} retried, {} failed\n\
+                 Game state source failures: {}",
+                snapshot.transitions,
+                millis(snapshot.latency_p50_millis),
+                millis(snapshot.latency_p95_millis),
+                snapshot.average_mute_wave().map_or_else(
+                    || "n/a".to_owned(),
+                    |wave| format!("{}ms", wave.as_millis())
+                ),
+                stats.requests,
+                stats.retries,
+                stats.failures,
+                snapshot.source_failures,
+            ))
+            .color(0x00_80_FF);
+
+        self.discord_client
+            .create_message(message.channel_id)
+            .embed(embed.build()?)?
+            .await?;
+
+        Ok(())
+    }
+
     fn match_members_to_players(
         &self,
         members: &[CachedMember],
     ) -> Option<Vec<(CachedMember, Option<Player>)>> {
+        self.match_members(members).map(|matches| {
+            matches
+                .into_iter()
+                .map(|(m, matched)| (m, matched.map(|(p, _)| p)))
+                .collect()
+        })
+    }
+
+    // Also says which matching strategy found each member's player
+    fn match_members(
+        &self,
+        members: &[CachedMember],
+    ) -> Option<Vec<(CachedMember, Option<(Player, &'static str)>)>> {
         let game_state = self.game_state_rx.borrow();
-        let players = match &*game_state {
+        let players = match game_state.as_ref().map(|s| &s.state) {
             Some(State::Lobby { players } | State::InGame { players, .. }) => Some(players),
             Some(_) | None => None,
         };
 
+        let forced = self.session.forced_names();
+        let matchers = self.matchers();
+
         players.map(|players| {
-            members
+            let igns = members
                 .iter()
-                .map(|m| {
-                    let ign = match self.player_names.read().get(&m.user_id) {
-                        Some(ign) => ign.clone(),
-                        None => (m, self.cache.user(m.user_id).unwrap()).known_as(),
-                    };
+                .map(|m| self.member_ign(m))
+                .collect::<Vec<_>>();
+            let names = players.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+            // A player forced onto someone else can't be claimed by this member
+            let allowed = |index: usize, name: &str| {
+                !forced
+                    .iter()
+                    .any(|(&user_id, forced)| user_id != members[index].user_id && forced == name)
+            };
+
+            matching::assign(&matchers, &igns, &names, allowed)
+                .into_iter()
+                .zip(members)
+                .map(|(assigned, m)| {
                     (
                         m.clone(),
-                        players.iter().find_map(
-                            |p| {
-                                if p.name == ign {
-                                    Some(p.clone())
-                                } else {
-                                    None
-                                }
-                            },
-                        ),
+                        assigned.map(|(player, strategy)| (players[player].clone(), strategy)),
                     )
                 })
                 .collect()
         })
     }
 
+    fn current_players(&self) -> Option<Vec<Player>> {
+        match self.game_state_rx.borrow().as_ref().map(|s| &s.state) {
+            Some(State::Lobby { players } | State::InGame { players, .. }) => Some(players.clone()),
+            Some(_) | None => None,
+        }
+    }
+
+    fn players_without_members(&self, members: &[CachedMember]) -> Option<Vec<Player>> {
+        let game_state = self.game_state_rx.borrow();
+        let players = match game_state.as_ref().map(|s| &s.state) {
+            Some(State::Lobby { players } | State::InGame { players, .. }) => players,
+            Some(_) | None => return None,
+        };
+
+        let igns = members
+            .iter()
+            .map(|m| self.member_ign(m))
+            .collect::<Vec<_>>();
+        let matchers = self.matchers();
+
+        Some(
+            players
+                .iter()
+                .filter(|p| {
+                    !igns
+                        .iter()
+                        .any(|ign| matchers.iter().any(|matcher| matcher.matches(ign, &p.name)))
+                })
+                .cloned()
+                .collect(),
+        )
+    }
+
+    // Servers that run ~normalize enable also match names that are only written differently
+    fn matchers(&self) -> Vec<Box<dyn Matcher>> {
+        let mut strategies = (*self.matching).clone();
+        let normalize = self
+            .home_guild
+            .map_or(false, |guild_id| self.store.guild(guild_id).normalize_names);
+        if normalize && !strategies.contains(&Strategy::Normalized) {
+            strategies.push(Strategy::Normalized);
+        }
+
+        strategies.into_iter().map(Strategy::build).collect()
+    }
+
+    fn member_ign(&self, member: &CachedMember) -> String {
+        if let Some(forced) = self.session.forced_name(member.user_id) {
+            return forced;
+        }
+
+        match self.session.player_name(member.user_id) {
+            Some(ign) => ign,
+            None => match self.cache.user(member.user_id) {
+                Some(user) => (member, user).known_as(),
+                None => member.nick.clone().unwrap_or_default(),
+            },
+        }
+    }
+
+    fn settings(&self) -> Settings {
+        self.settings.read().clone()
+    }
+
+    fn living_channels(&self) -> Vec<ChannelId> {
+        let settings = self.settings();
+        let mut channels = settings.split_channels;
+        channels.insert(0, settings.living_channel);
+        channels.extend(settings.participant_channels);
+        channels
+    }
+
+    // The dead have usually been moved out of the living channels, so they're looked for in the
+    // dead channel and lounge too
+    fn member_playing(&self, name: &str) -> Option<CachedMember> {
+        let settings = self.settings();
+        let mut members = self.get_living_members();
+        members.extend(self.get_members_in_channel(settings.dead_channel));
+        if let Some(lounge) = settings.lounge_channel {
+            members.extend(self.get_members_in_channel(lounge));
+        }
+
+        self.match_members_to_players(&members)?
+            .into_iter()
+            .find(|(_, p)| p.as_ref().map_or(false, |p| p.name == name))
+            .map(|(m, _)| m)
+    }
+
+    fn get_living_members(&self) -> Vec<CachedMember> {
+        self.living_channels()
+            .into_iter()
+            .flat_map(|channel| self.get_members_in_channel(channel))
+            .collect()
+    }
+
+    // The bot is never muted or moved, even if it's listed, as it has no say in the game. Other
+    // bots are left alone unless they're listed in muted_bots, e.g. a soundboard that should
+    // fall silent with everyone else
+    fn is_excluded(&self, user: &User) -> bool {
+        user.id == self.bot_id || (user.bot && !self.muted_bots.contains(&user.id))
+    }
+
     fn get_members_in_channel(&self, channel: ChannelId) -> Vec<CachedMember> {
         self.cache
             .voice_channel_states(channel)
             .map_or(Vec::new(), |vs| {
+                // Members or users missing from the cache are skipped rather than trusted
                 vs.iter()
-                    .map(|vs| {
-                        (
-                            self.cache.member(vs.guild_id.unwrap(), vs.user_id).unwrap(),
-                            self.cache.user(vs.user_id).unwrap(),
-                        )
+                    .filter_map(|vs| {
+                        Some((
+                            self.cache.member(vs.guild_id?, vs.user_id)?,
+                            self.cache.user(vs.user_id)?,
+                        ))
+                    })
+                    .filter_map(|(m, u)| {
+                        if self.is_excluded(&u)
+                            || self.session.is_kicked(m.user_id)
+                            || self.session.is_parked(m.user_id)
+                        {
+                            None
+                        } else {
+                            Some(m)
+                        }
                     })
-                    .filter_map(|(m, u)| if u.bot { None } else { Some(m) })
                     .collect()
             })
     }
 
-    async fn batch<Fut, Out>(&self, futs: Vec<Fut>) -> Vec<Out>
-    where
-        Fut: Future<Output = TwiResult<Out>>,
-        Out: Debug,
-    {
-        let (successes, errors) = futures::future::join_all(futs)
-            .await
-            .into_iter()
-            .partition::<Vec<_>, _>(TwiResult::is_ok);
+    // Returns how many of the updates failed
+    async fn batch(&self, mut updates: Vec<MemberUpdate>) -> usize {
+        let interval = match self.mute_pacing {
+            MutePacing::Together => Duration::default(),
+            MutePacing::InOrder => {
+                updates.sort_by_cached_key(|update| self.sort_name(update.member()));
+                self.mute_pacing_interval
+            }
+        };
+
+        // Each update is started a little after the last rather than waiting for it, so the order
+        // holds without the wave taking any longer than the pacing itself
+        let mut delay = Duration::default();
+        let mut requests = Vec::with_capacity(updates.len());
+        for update in updates {
+            requests.push(async move {
+                sleep(delay).await;
+                self.discord_client.update_member(update).await
+            });
+            delay += interval;
+        }
 
-        let errors = errors
+        let errors = futures::future::join_all(requests)
+            .await
             .into_iter()
-            .map(TwiResult::unwrap_err)
+            .filter_map(TwiResult::err)
             .collect::<Vec<_>>();
+        let failed = errors.len();
 
         if !errors.is_empty() {
             let _e_msg = self
-                .discord_client
-                .create_message(self.broadcast_channel)
-                .content("Errors occurred during batch operation, check logs")
-                .unwrap()
+                .notify("Errors occurred during batch operation, check logs")
                 .await;
             for error in errors {
                 tracing::warn!("{}", error);
             }
         }
 
-        successes.into_iter().map(TwiResult::unwrap).collect()
+        failed
+    }
+
+    fn sort_name(&self, (guild_id, user_id): (GuildId, UserId)) -> String {
+        match (
+            self.cache.member(guild_id, user_id),
+            self.cache.user(user_id),
+        ) {
+            (Some(member), Some(user)) => (&member, user).known_as().to_lowercase(),
+            _ => user_id.to_string(),
+        }
+    }
+
+    // Members can block the bot or only accept DMs from friends. Once Discord refuses one they're
+    // left alone, rather than the same error being logged every game. Returns whether it was sent
+    async fn dm(&self, user_id: UserId, content: String) -> Result<bool> {
+        self.dm_attachment(user_id, content, None).await
+    }
+
+    async fn dm_attachment(
+        &self,
+        user_id: UserId,
+        content: String,
+        attachment: Option<(&str, Vec<u8>)>,
+    ) -> Result<bool> {
+        if self.store.user(user_id).dms_closed {
+            tracing::debug!("Not sending a DM to {}, their DMs are closed", user_id);
+            return Ok(false);
+        }
+
+        let channel = self.discord_client.create_private_channel(user_id).await?;
+        let create = self
+            .discord_client
+            .create_message(channel.id)
+            .content(content)?;
+        let create = match attachment {
+            Some((name, contents)) => create.attachment(name, contents),
+            None => create,
+        };
+
+        match create.await {
+            Ok(_) => Ok(true),
+            Err(why) if is_forbidden(&why) => {
+                tracing::info!("{} doesn't accept DMs from the bot", user_id);
+                self.store
+                    .update_user(user_id, |user| user.dms_closed = true)?;
+                Ok(false)
+            }
+            Err(why) => Err(why.into()),
+        }
+    }
+
+    // For DMs someone asked for, so they aren't left waiting for one that was never sent
+    async fn dms_closed(&self, message: &Message) -> Result<()> {
+        message
+            .reply(
+                &self.discord_client,
+                "Your DMs are closed to the bot, DM it anything and try again",
+            )?
+            .await?;
+
+        Ok(())
+    }
+
+    // Operational notices go to the broadcast channel, or to the owners directly if there isn't one
+    pub async fn notify(&self, content: &str) -> Result<()> {
+        match self.broadcast_channel {
+            // Posted directly, as the notice may be about the announcer itself
+            Some(broadcast_channel) => {
+                self.discord_client
+                    .create_message(broadcast_channel)
+                    .content(content)?
+                    .await?;
+            }
+            None => {
+                for &owner in &*self.owners {
+                    self.dm(owner, content.to_owned()).await?;
+                }
+            }
+        }
+
+        Ok(())
     }
 }
+
+async fn is_voice_channel_in(http: &Http, guild_id: GuildId, channel: ChannelId) -> Result<bool> {
+    Ok(matches!(
+        http.channel(channel).await?,
+        Some(Channel::Guild(GuildChannel::Voice(vc))) if vc.guild_id == Some(guild_id)
+    ))
+}
+
+// Whoever pressed a button, in a server or a DM
+fn interaction_user(interaction: &MessageComponentInteraction) -> Option<UserId> {
+    interaction
+        .member
+        .as_ref()
+        .and_then(|member| member.user.as_ref())
+        .or_else(|| interaction.user.as_ref())
+        .map(|user| user.id)
+}
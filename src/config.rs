@@ -1,28 +1,856 @@
-use crate::Result;
+use crate::{
+    emoji::{Emoji, Status},
+    matching::Strategy,
+    rules::{HouseRule, RULE_KEYS, TRIGGERS},
+    source::SourceConfig,
+    utils::{distance, parse_duration},
+    Result,
+};
 
-use std::{fs::File, io::Read, path::Path};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    error::Error,
+    fmt,
+    fs::File,
+    io::Read,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 
 use serde::Deserialize;
-use twilight_model::id::ChannelId;
+use toml::{value::Table, Value};
+use twilight_cache_inmemory::ResourceType;
+use twilight_gateway::Intents;
+use twilight_http::Client;
+use twilight_model::id::{ChannelId, UserId};
 
 #[derive(Deserialize)]
 pub struct Config {
     pub token: String,
-    pub broadcast_channel: ChannelId,
+    pub broadcast_channel: Option<ChannelId>,
     pub living_channel: ChannelId,
+    #[serde(default)]
+    pub split_channels: Vec<ChannelId>,
+    #[serde(default)]
+    pub participant_channels: Vec<ChannelId>,
     pub dead_channel: ChannelId,
+    pub lounge_channel: Option<ChannelId>,
+    pub lounge_after_meetings: Option<u32>,
+    pub afk_after_meetings: Option<u32>,
+    #[serde(default)]
+    pub announce_unmatched: bool,
+    #[serde(default)]
+    pub announce_impostors: bool,
+    #[serde(default)]
+    pub reveal_roles: bool,
+    #[serde(default)]
+    pub hush_voting: bool,
+    #[serde(default)]
+    pub nickname_status: bool,
+    #[serde(default)]
+    pub status_board: bool,
+    pub transcript_path: Option<PathBuf>,
+    #[serde(default)]
+    pub transcript_upload: bool,
+    #[serde(default = "default_sources")]
+    pub sources: Vec<SourceConfig>,
+    #[serde(default)]
+    pub control_permission: ControlPermission,
+    #[serde(default)]
+    pub ignored_command_feedback: CommandFeedback,
+    #[serde(default = "default_reconnect_grace_secs")]
+    pub reconnect_grace_secs: u64,
+    #[serde(default = "default_ident_ttl_days")]
+    pub ident_ttl_days: u64,
+    #[serde(default = "default_max_state_age_secs")]
+    pub max_state_age_secs: u64,
+    #[serde(default = "default_store_path")]
+    pub store_path: PathBuf,
+    #[serde(default = "default_lock_path")]
+    pub lock_path: PathBuf,
+    #[serde(default)]
+    pub late_joiner_policy: LateJoinerPolicy,
+    pub grpc_address: Option<SocketAddr>,
+    pub control_socket: Option<PathBuf>,
+    pub telemetry_endpoint: Option<String>,
+    pub sentry_dsn: Option<String>,
+    #[serde(default)]
+    pub check_for_updates: bool,
+    #[serde(default)]
+    pub voice_status: bool,
+    pub kick_channel: Option<ChannelId>,
+    pub afk_channel: Option<ChannelId>,
+    pub cue_channel: Option<ChannelId>,
+    #[serde(default = "default_afk_channel_after_secs")]
+    pub afk_channel_after_secs: u64,
+    pub archive_channel: Option<ChannelId>,
+    #[serde(default)]
+    pub quiet_channels: Vec<ChannelId>,
+    #[serde(default)]
+    pub muted_bots: Vec<UserId>,
+    #[serde(default = "default_quiet_slow_mode_secs")]
+    pub quiet_slow_mode_secs: u64,
+    #[serde(default = "default_session_idle_mins")]
+    pub session_idle_mins: u64,
+    #[serde(default = "default_lobby_cleanup_secs")]
+    pub lobby_cleanup_secs: u64,
+    #[serde(default)]
+    pub kick_mute: bool,
+    #[serde(default)]
+    pub require_arming: bool,
+    #[serde(default)]
+    pub mute_in_place: bool,
+    #[serde(default)]
+    pub mute_pacing: MutePacing,
+    #[serde(default = "default_mute_pacing_ms")]
+    pub mute_pacing_ms: u64,
+    #[serde(default = "default_failure_budget")]
+    pub failure_budget: u32,
+    #[serde(default = "default_lobby_size")]
+    pub lobby_size: usize,
+    #[serde(default = "default_meeting_end_delay_secs")]
+    pub meeting_end_delay_secs: u64,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    pub emoji: BTreeMap<String, String>,
+    #[serde(default)]
+    pub rules: Vec<HouseRule>,
+    #[serde(default = "default_matching")]
+    pub matching: Vec<Strategy>,
+    #[serde(default = "default_intents")]
+    pub intents: Vec<GatewayIntent>,
+    #[serde(default = "default_cache_resources")]
+    pub cache_resources: Vec<CacheResource>,
+}
+
+fn default_store_path() -> PathBuf {
+    PathBuf::from("./Store.toml")
+}
+
+fn default_reconnect_grace_secs() -> u64 {
+    60
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ControlPermission {
+    Anyone,
+    Voice,
+}
+
+impl Default for ControlPermission {
+    fn default() -> Self {
+        ControlPermission::Anyone
+    }
+}
+
+fn default_sources() -> Vec<SourceConfig> {
+    vec![SourceConfig::Memory {
+        pid: None,
+        exe: None,
+    }]
 }
 
 impl Config {
-    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<Self> {
         let mut file = File::open(path.as_ref())?;
         let mut contents = Vec::new();
         file.read_to_end(&mut contents)?;
 
         let config_str = String::from_utf8(contents)?;
 
-        let config: Config = toml::from_str(&config_str)?;
+        let mut value: Value = toml::from_str(&config_str)?;
+
+        let problems = validate(&value);
+        if !problems.is_empty() {
+            return Err(Box::new(Invalid(problems)));
+        }
+
+        if let Some(table) = value.as_table_mut() {
+            for_each_setting(table, &mut |key: &str, value: &mut Value| {
+                if let (Some((unit, _)), Value::String(duration)) = (duration_unit(key), &*value) {
+                    if let Some(amount) = duration_in(duration, unit) {
+                        *value = Value::Integer(amount);
+                    }
+                }
+            });
+            resolve_channel_names(table).await?;
+        }
+
+        // Anything validation doesn't know to look for, e.g. a misspelt policy, is still caught here
+        let config: Config = value
+            .try_into()
+            .map_err(|why: toml::de::Error| Invalid(vec![why.to_string()]))?;
 
         Ok(config)
     }
+
+    pub fn intents(&self) -> Intents {
+        self.intents
+            .iter()
+            .fold(Intents::empty(), |intents, intent| {
+                intents | intent.intents()
+            })
+    }
+
+    pub fn cache_resources(&self) -> ResourceType {
+        self.cache_resources
+            .iter()
+            .fold(ResourceType::empty(), |resources, resource| {
+                resources | resource.resource_type()
+            })
+    }
+}
+
+const REQUIRED_KEYS: &[&str] = &["token", "living_channel", "dead_channel"];
+
+const KEYS: &[&str] = &[
+    "token",
+    "broadcast_channel",
+    "living_channel",
+    "split_channels",
+    "participant_channels",
+    "dead_channel",
+    "lounge_channel",
+    "lounge_after_meetings",
+    "afk_after_meetings",
+    "announce_unmatched",
+    "announce_impostors",
+    "reveal_roles",
+    "hush_voting",
+    "nickname_status",
+    "status_board",
+    "transcript_path",
+    "transcript_upload",
+    "sources",
+    "control_permission",
+    "ignored_command_feedback",
+    "reconnect_grace_secs",
+    "ident_ttl_days",
+    "max_state_age_secs",
+    "store_path",
+    "lock_path",
+    "late_joiner_policy",
+    "grpc_address",
+    "control_socket",
+    "telemetry_endpoint",
+    "sentry_dsn",
+    "check_for_updates",
+    "voice_status",
+    "kick_channel",
+    "afk_channel",
+    "cue_channel",
+    "afk_channel_after_secs",
+    "archive_channel",
+    "quiet_channels",
+    "muted_bots",
+    "quiet_slow_mode_secs",
+    "session_idle_mins",
+    "lobby_cleanup_secs",
+    "kick_mute",
+    "require_arming",
+    "mute_in_place",
+    "mute_pacing",
+    "mute_pacing_ms",
+    "failure_budget",
+    "lobby_size",
+    "meeting_end_delay_secs",
+    "profiles",
+    "emoji",
+    "rules",
+    "matching",
+    "intents",
+    "cache_resources",
+];
+
+const PROFILE_KEYS: &[&str] = &[
+    "living_channel",
+    "split_channels",
+    "participant_channels",
+    "dead_channel",
+    "lounge_channel",
+    "reconnect_grace_secs",
+    "meeting_end_delay_secs",
+    "late_joiner_policy",
+];
+
+// Every problem found with the config, so they can all be fixed in one go
+#[derive(Debug)]
+pub struct Invalid(Vec<String>);
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The config has {} problem(s):", self.0.len())?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Invalid {}
+
+fn validate(value: &Value) -> Vec<String> {
+    let table = match value.as_table() {
+        Some(table) => table,
+        None => return vec!["the config must be a table of keys".to_owned()],
+    };
+
+    let mut problems = Vec::new();
+
+    for key in REQUIRED_KEYS {
+        if !table.contains_key(*key) {
+            problems.push(format!("`{}` is missing", key));
+        }
+    }
+
+    validate_keys(table, KEYS, "", &mut problems);
+
+    match table.get("profiles") {
+        Some(Value::Table(profiles)) => {
+            for (name, profile) in profiles {
+                let prefix = format!("profiles.{}.", name);
+                match profile {
+                    Value::Table(profile) => {
+                        validate_keys(profile, PROFILE_KEYS, &prefix, &mut problems);
+                    }
+                    _ => problems.push(format!("`profiles.{}` must be a table", name)),
+                }
+            }
+        }
+        Some(_) => problems.push("`profiles` must be a table".to_owned()),
+        None => {}
+    }
+
+    match table.get("emoji") {
+        Some(Value::Table(emoji)) => {
+            for (status, emoji) in emoji {
+                if Status::from_name(status).is_none() {
+                    problems.push(format!(
+                        "`emoji.{}` isn't something the bot shows, it can be one of: {}",
+                        status,
+                        Status::ALL
+                            .iter()
+                            .map(|status| status.name())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                } else if let Err(why) = emoji
+                    .as_str()
+                    .map_or(Err("must be a string"), str::parse::<Emoji>)
+                {
+                    problems.push(format!("`emoji.{}` {}", status, why));
+                }
+            }
+        }
+        Some(_) => problems.push("`emoji` must be a table".to_owned()),
+        None => {}
+    }
+
+    match table.get("rules") {
+        Some(Value::Array(rules)) => {
+            for (index, rule) in rules.iter().enumerate() {
+                let prefix = format!("rules.{}.", index + 1);
+                let rule = match rule {
+                    Value::Table(rule) => rule,
+                    _ => {
+                        problems.push(format!("`rules.{}` must be a table", index + 1));
+                        continue;
+                    }
+                };
+                validate_keys(rule, RULE_KEYS, &prefix, &mut problems);
+
+                let on = rule.get("on").and_then(Value::as_str);
+                if !on.map_or(false, |on| TRIGGERS.contains(&on)) {
+                    problems.push(format!(
+                        "`{}on` must be one of: {}",
+                        prefix,
+                        TRIGGERS.join(", ")
+                    ));
+                }
+                if rule.get("give_role").map_or(false, |role| !is_id(role)) {
+                    problems.push(format!(
+                        "`{}give_role` must be a role ID, e.g. \"123456789012345678\"",
+                        prefix
+                    ));
+                }
+                if rule.contains_key("give_role") && on != Some("death") {
+                    problems.push(format!(
+                        "`{}give_role` needs someone to give it to, so only works `on = \"death\"`",
+                        prefix
+                    ));
+                }
+                if rule.contains_key("say") && !table.contains_key("broadcast_channel") {
+                    problems.push(format!("`{}say` needs a `broadcast_channel`", prefix));
+                }
+            }
+        }
+        Some(_) => problems.push("`rules` must be a list of [[rules]] tables".to_owned()),
+        None => {}
+    }
+
+    problems
+}
+
+fn validate_keys(table: &Table, known: &[&str], prefix: &str, problems: &mut Vec<String>) {
+    for (key, value) in table {
+        if !known.contains(&key.as_str()) {
+            problems.push(match suggest(key, known) {
+                Some(suggestion) => format!(
+                    "`{}{}` isn't a setting, did you mean `{}{}`?",
+                    prefix, key, prefix, suggestion
+                ),
+                None => format!("`{}{}` isn't a setting", prefix, key),
+            });
+        } else if key.ends_with("_channel") {
+            if !is_id(value) && channel_name(value).is_none() {
+                problems.push(format!(
+                    "`{}{}` must be a channel ID, e.g. \"123456789012345678\", or name, e.g. \
+                     \"#among-us\"",
+                    prefix, key
+                ));
+            }
+        } else if key.ends_with("_channels") {
+            match value.as_array() {
+                Some(ids) if ids.iter().all(|id| is_id(id) || channel_name(id).is_some()) => {}
+                _ => problems.push(format!(
+                    "`{}{}` must be a list of channel IDs or names, e.g. \
+                     [\"123456789012345678\", \"#among-us\"]",
+                    prefix, key
+                )),
+            }
+        } else if key == "muted_bots" {
+            match value.as_array() {
+                Some(ids) if ids.iter().all(is_id) => {}
+                _ => problems.push(format!(
+                    "`{}{}` must be a list of user IDs, e.g. [\"123456789012345678\"]",
+                    prefix, key
+                )),
+            }
+        } else if let Some((unit, unit_name)) = duration_unit(key) {
+            let valid = match value {
+                Value::Integer(amount) => *amount >= 0,
+                Value::String(duration) => duration_in(duration, unit).is_some(),
+                _ => false,
+            };
+            if !valid {
+                problems.push(format!(
+                    "`{}{}` must be a whole number of {}, e.g. 8, or a duration like \"8s\", \
+                     \"3m\" or \"1h30m\"",
+                    prefix, key, unit_name
+                ));
+            }
+        }
+    }
+}
+
+// Settings ending in a unit take a number of that unit, or a duration that's a whole number of it
+fn duration_unit(key: &str) -> Option<(u64, &'static str)> {
+    if key.ends_with("_secs") {
+        Some((1, "seconds"))
+    } else if key.ends_with("_mins") {
+        Some((60, "minutes"))
+    } else if key.ends_with("_days") {
+        Some((24 * 60 * 60, "days"))
+    } else {
+        None
+    }
+}
+
+fn duration_in(duration: &str, unit: u64) -> Option<i64> {
+    let secs = parse_duration(duration)?.as_secs();
+    if secs % unit != 0 {
+        return None;
+    }
+
+    i64::try_from(secs / unit).ok()
+}
+
+// Names are written as Discord shows them, with a #, so they can't be mistaken for IDs
+fn channel_name(value: &Value) -> Option<&str> {
+    value
+        .as_str()?
+        .strip_prefix('#')
+        .filter(|name| !name.is_empty())
+}
+
+// Each top level and profile setting
+fn for_each_setting(table: &mut Table, f: &mut impl FnMut(&str, &mut Value)) {
+    for (key, value) in table.iter_mut() {
+        match (key.as_str(), value) {
+            ("profiles", Value::Table(profiles)) => {
+                for profile in profiles.values_mut() {
+                    if let Value::Table(profile) = profile {
+                        for (key, value) in profile.iter_mut() {
+                            f(key, value);
+                        }
+                    }
+                }
+            }
+            (key, value) => f(key, value),
+        }
+    }
+}
+
+// Channels given by name are looked up in the servers the bot is in, before anything else
+// needs them
+async fn resolve_channel_names(table: &mut Table) -> Result<()> {
+    let mut names = Vec::new();
+    for_each_setting(table, &mut |key: &str, value: &mut Value| {
+        if key.ends_with("_channel") {
+            names.extend(channel_name(value).map(str::to_owned));
+        } else if let (true, Value::Array(values)) = (key.ends_with("_channels"), &*value) {
+            names.extend(values.iter().filter_map(channel_name).map(str::to_owned));
+        }
+    });
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    let token = table
+        .get("token")
+        .and_then(Value::as_str)
+        .ok_or("`token` must be a string")?;
+    let client = Client::new(token.to_owned());
+
+    let mut channels = Vec::new();
+    for guild in client.current_user_guilds().await? {
+        for channel in client.guild_channels(guild.id).await? {
+            channels.push((channel.name().to_owned(), channel.id()));
+        }
+    }
+
+    let mut problems = Vec::new();
+    let mut resolved = BTreeMap::new();
+    for name in names {
+        let matching = channels
+            .iter()
+            .filter(|(channel, _)| *channel == name)
+            .map(|(_, id)| *id)
+            .collect::<Vec<_>>();
+        match matching[..] {
+            [id] => {
+                resolved.insert(name, id);
+            }
+            [] => problems.push(format!("there is no channel called #{}", name)),
+            _ => problems.push(format!(
+                "more than one channel is called #{}, use its ID instead",
+                name
+            )),
+        }
+    }
+    if !problems.is_empty() {
+        return Err(Box::new(Invalid(problems)));
+    }
+
+    let resolve = |value: &mut Value| {
+        if let Some(id) = channel_name(value).and_then(|name| resolved.get(name)) {
+            *value = Value::String(id.to_string());
+        }
+    };
+    for_each_setting(table, &mut |key: &str, value: &mut Value| {
+        if key.ends_with("_channel") {
+            resolve(value);
+        } else if let (true, Value::Array(values)) = (key.ends_with("_channels"), value) {
+            values.iter_mut().for_each(&resolve);
+        }
+    });
+
+    Ok(())
+}
+
+// IDs can be written as strings, as Discord shows them, or as numbers
+fn is_id(value: &Value) -> bool {
+    match value {
+        Value::String(id) => !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()),
+        Value::Integer(id) => *id > 0,
+        _ => false,
+    }
+}
+
+// The closest known key to a misspelt one, if it's close enough to be what was meant
+fn suggest<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|candidate| (distance(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Overrides for the top level settings, switched between per guild with ~profile
+#[derive(Clone, Deserialize)]
+pub struct Profile {
+    pub living_channel: Option<ChannelId>,
+    pub split_channels: Option<Vec<ChannelId>>,
+    pub participant_channels: Option<Vec<ChannelId>>,
+    pub dead_channel: Option<ChannelId>,
+    pub lounge_channel: Option<ChannelId>,
+    pub reconnect_grace_secs: Option<u64>,
+    pub meeting_end_delay_secs: Option<u64>,
+    pub late_joiner_policy: Option<LateJoinerPolicy>,
+}
+
+// How the bot lets people know it saw a command it won't run
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommandFeedback {
+    Silent,
+    React,
+    Reply,
+}
+
+impl Default for CommandFeedback {
+    fn default() -> Self {
+        CommandFeedback::Silent
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LateJoinerPolicy {
+    Mute,
+    Ignore,
+    MoveToDead,
+}
+
+impl Default for LateJoinerPolicy {
+    fn default() -> Self {
+        LateJoinerPolicy::Mute
+    }
+}
+
+// How the updates in each wave of a plan are sent: all at once, finishing in whatever order
+// Discord gets to them, or started one at a time in order of name
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MutePacing {
+    Together,
+    InOrder,
+}
+
+impl Default for MutePacing {
+    fn default() -> Self {
+        MutePacing::Together
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GatewayIntent {
+    Guilds,
+    GuildMembers,
+    GuildMessages,
+    GuildVoiceStates,
+    DirectMessages,
+}
+
+impl GatewayIntent {
+    fn intents(self) -> Intents {
+        match self {
+            GatewayIntent::Guilds => Intents::GUILDS,
+            GatewayIntent::GuildMembers => Intents::GUILD_MEMBERS,
+            GatewayIntent::GuildMessages => Intents::GUILD_MESSAGES,
+            GatewayIntent::GuildVoiceStates => Intents::GUILD_VOICE_STATES,
+            GatewayIntent::DirectMessages => Intents::DIRECT_MESSAGES,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheResource {
+    Channel,
+    Guild,
+    Member,
+    User,
+    VoiceState,
+}
+
+impl CacheResource {
+    fn resource_type(self) -> ResourceType {
+        match self {
+            CacheResource::Channel => ResourceType::CHANNEL,
+            CacheResource::Guild => ResourceType::GUILD,
+            CacheResource::Member => ResourceType::MEMBER,
+            CacheResource::User => ResourceType::USER,
+            CacheResource::VoiceState => ResourceType::VOICE_STATE,
+        }
+    }
+}
+
+fn default_ident_ttl_days() -> u64 {
+    30
+}
+
+fn default_max_state_age_secs() -> u64 {
+    10
+}
+
+fn default_afk_channel_after_secs() -> u64 {
+    180
+}
+
+fn default_mute_pacing_ms() -> u64 {
+    50
+}
+
+fn default_lobby_cleanup_secs() -> u64 {
+    120
+}
+
+fn default_failure_budget() -> u32 {
+    10
+}
+
+fn default_lobby_size() -> usize {
+    15
+}
+
+fn default_matching() -> Vec<Strategy> {
+    vec![Strategy::Exact]
+}
+
+fn default_intents() -> Vec<GatewayIntent> {
+    vec![
+        GatewayIntent::Guilds,
+        GatewayIntent::GuildMembers,
+        GatewayIntent::GuildMessages,
+        GatewayIntent::GuildVoiceStates,
+    ]
+}
+
+fn default_cache_resources() -> Vec<CacheResource> {
+    vec![
+        CacheResource::Channel,
+        CacheResource::Guild,
+        CacheResource::Member,
+        CacheResource::User,
+        CacheResource::VoiceState,
+    ]
+}
+
+fn default_meeting_end_delay_secs() -> u64 {
+    10
+}
+
+fn default_quiet_slow_mode_secs() -> u64 {
+    30
+}
+
+fn default_session_idle_mins() -> u64 {
+    60
+}
+
+fn default_lock_path() -> PathBuf {
+    PathBuf::from("./taskinator.lock")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINIMAL: &str = r#"
+        token = "token"
+        living_channel = "123456789012345678"
+        dead_channel = "123456789012345679"
+    "#;
+
+    fn problems(extra: &str) -> Vec<String> {
+        validate(&format!("{}{}", MINIMAL, extra).parse::<Value>().unwrap())
+    }
+
+    #[test]
+    fn a_minimal_config_is_valid() {
+        assert!(problems("").is_empty());
+    }
+
+    #[test]
+    fn missing_keys_are_reported() {
+        assert_eq!(
+            validate(&"".parse::<Value>().unwrap()),
+            vec![
+                "`token` is missing",
+                "`living_channel` is missing",
+                "`dead_channel` is missing",
+            ]
+        );
+    }
+
+    #[test]
+    fn misspelt_keys_get_a_suggestion() {
+        assert_eq!(
+            problems("broadcast_chanel = \"123\""),
+            vec!["`broadcast_chanel` isn't a setting, did you mean `broadcast_channel`?"]
+        );
+        assert_eq!(
+            problems("something_else = true"),
+            vec!["`something_else` isn't a setting"]
+        );
+    }
+
+    #[test]
+    fn channels_are_ids_or_names() {
+        assert!(problems("broadcast_channel = 123").is_empty());
+        assert!(problems("broadcast_channel = \"#among-us\"").is_empty());
+        assert!(problems("split_channels = [\"123\", \"#among-us-2\"]").is_empty());
+
+        assert_eq!(problems("broadcast_channel = \"among-us\"").len(), 1);
+        assert_eq!(problems("broadcast_channel = \"#\"").len(), 1);
+        assert_eq!(problems("split_channels = \"123\"").len(), 1);
+        assert_eq!(problems("muted_bots = [\"#bot\"]").len(), 1);
+    }
+
+    #[test]
+    fn durations_must_be_whole_units() {
+        assert!(problems("reconnect_grace_secs = 60").is_empty());
+        assert!(problems("reconnect_grace_secs = \"1m30s\"").is_empty());
+        assert!(problems("session_idle_mins = \"2h\"").is_empty());
+
+        assert_eq!(problems("reconnect_grace_secs = -1").len(), 1);
+        assert_eq!(problems("reconnect_grace_secs = \"1m30\"").len(), 1);
+        assert_eq!(problems("session_idle_mins = \"90s\"").len(), 1);
+        assert_eq!(problems("ident_ttl_days = \"1h\"").len(), 1);
+    }
+
+    #[test]
+    fn profiles_only_take_profile_keys() {
+        assert!(problems("[profiles.quiet]\nlounge_channel = \"123\"").is_empty());
+        assert_eq!(
+            problems("[profiles.quiet]\ntoken = \"token\""),
+            vec!["`profiles.quiet.token` isn't a setting"]
+        );
+        assert_eq!(problems("profiles = 1"), vec!["`profiles` must be a table"]);
+    }
+
+    #[test]
+    fn emoji_must_be_for_something_shown() {
+        assert!(problems("[emoji]\ndead = \"<:dead:123456789012345678>\"").is_empty());
+        assert_eq!(
+            problems("[emoji]\ndead = \"x\""),
+            vec![
+                "`emoji.dead` must be an emoji, or a server emoji like <:name:123456789012345678>"
+            ]
+        );
+        assert_eq!(problems("[emoji]\nzombie = \"🧟\"").len(), 1);
+    }
+
+    #[test]
+    fn rules_are_checked_against_their_trigger() {
+        assert!(problems("[[rules]]\non = \"death\"\nnth = 1\ngive_role = \"123\"").is_empty());
+        assert_eq!(
+            problems("[[rules]]\non = \"deaths\""),
+            vec!["`rules.1.on` must be one of: game-start, death, meeting, game-end"]
+        );
+        assert_eq!(
+            problems("[[rules]]\non = \"meeting\"\ngive_role = \"123\""),
+            vec!["`rules.1.give_role` needs someone to give it to, so only works `on = \"death\"`"]
+        );
+        assert_eq!(
+            problems("[[rules]]\non = \"game-end\"\nsay = \"GG\""),
+            vec!["`rules.1.say` needs a `broadcast_channel`"]
+        );
+    }
 }
@@ -0,0 +1,95 @@
+use std::{collections::HashMap, time::Duration};
+
+use parking_lot::Mutex;
+use twilight_mention::Mention;
+use twilight_model::{
+    application::component::{button::ButtonStyle, ActionRow, Button, Component},
+    id::{GuildId, MessageId, UserId},
+};
+
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub const CONFIRM_ID: &str = "taskinator-confirm";
+pub const CANCEL_ID: &str = "taskinator-cancel";
+
+#[derive(Clone, Copy)]
+pub enum Action {
+    Stop,
+    EndGame,
+    Kick {
+        guild_id: Option<GuildId>,
+        target: UserId,
+    },
+    // A member swapping players with someone else, which that member has to accept
+    Swap {
+        first: UserId,
+        second: UserId,
+        keep_names: bool,
+    },
+}
+
+impl Action {
+    pub fn prompt(self) -> String {
+        match self {
+            Action::Stop => "Shut the bot down?".to_owned(),
+            Action::EndGame => "End the game and unmute everyone?".to_owned(),
+            Action::Kick { .. } => "Remove this member from the session?".to_owned(),
+            // Mentioned so whoever has to accept it notices
+            Action::Swap { first, second, .. } => format!(
+                "Swap the players {} and {} are matched to?",
+                first.mention(),
+                second.mention()
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Pending {
+    pub action: Action,
+    // Only whoever ran the command can confirm it, except a swap is confirmed by the other member
+    pub confirmer: UserId,
+}
+
+// Destructive commands waiting on a button press, keyed by the confirmation message
+#[derive(Default)]
+pub struct Confirmations {
+    pending: Mutex<HashMap<MessageId, Pending>>,
+}
+
+impl Confirmations {
+    pub fn insert(&self, message_id: MessageId, pending: Pending) {
+        self.pending.lock().insert(message_id, pending);
+    }
+
+    pub fn get(&self, message_id: MessageId) -> Option<Pending> {
+        self.pending.lock().get(&message_id).copied()
+    }
+
+    pub fn remove(&self, message_id: MessageId) -> Option<Pending> {
+        self.pending.lock().remove(&message_id)
+    }
+}
+
+pub fn buttons() -> Vec<Component> {
+    vec![Component::ActionRow(ActionRow {
+        components: vec![
+            Component::Button(Button {
+                custom_id: Some(CONFIRM_ID.to_owned()),
+                disabled: false,
+                emoji: None,
+                label: Some("Confirm".to_owned()),
+                style: ButtonStyle::Danger,
+                url: None,
+            }),
+            Component::Button(Button {
+                custom_id: Some(CANCEL_ID.to_owned()),
+                disabled: false,
+                emoji: None,
+                label: Some("Cancel".to_owned()),
+                style: ButtonStyle::Secondary,
+                url: None,
+            }),
+        ],
+    })]
+}
@@ -0,0 +1,177 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::{bot::Bot, signals, Result};
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Error>,
+}
+
+#[derive(Serialize)]
+struct Error {
+    code: i64,
+    message: String,
+}
+
+impl Response {
+    fn new(id: Value, result: std::result::Result<Value, Error>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+fn error(code: i64, message: impl Into<String>) -> Error {
+    Error {
+        code,
+        message: message.into(),
+    }
+}
+
+fn status(bot: &Bot) -> Value {
+    json!({
+        "phase": bot.phase(),
+        "paused": bot.is_paused(),
+        "players": bot
+            .roster()
+            .into_iter()
+            .map(|entry| json!({
+                "name": entry.name,
+                "dead": entry.dead,
+                "discord_user_id": entry.user_id.map(|id| id.0.to_string()),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+async fn call(bot: &Bot, request: Request) -> std::result::Result<Value, Error> {
+    match request.method.as_str() {
+        "status" => {}
+        "pause" => match request.params.get("paused").and_then(Value::as_bool) {
+            Some(paused) => bot.set_paused(paused),
+            None => return Err(error(INVALID_PARAMS, "Expected {\"paused\": bool}")),
+        },
+        "end-game" => bot.force_end_game().await,
+        "reload-config" => signals::reload(bot)
+            .await
+            .map_err(|why| error(SERVER_ERROR, why.to_string()))?,
+        method => {
+            return Err(error(
+                METHOD_NOT_FOUND,
+                format!("There is no method called {}", method),
+            ))
+        }
+    }
+
+    Ok(status(bot))
+}
+
+// One request per line, each answered with one response line
+async fn handle(bot: Bot, stream: impl AsyncRead + AsyncWrite) -> Result<()> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let id = request.id.clone();
+                Response::new(id, call(&bot, request).await)
+            }
+            Err(why) => Response::new(Value::Null, Err(error(PARSE_ERROR, why.to_string()))),
+        };
+
+        let mut response = serde_json::to_vec(&response)?;
+        response.push(b'\n');
+        writer.write_all(&response).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+pub async fn serve(bot: Bot, path: PathBuf) -> Result<()> {
+    use std::{
+        fs::{self, Permissions},
+        os::unix::fs::{FileTypeExt, PermissionsExt},
+    };
+    use tokio::net::UnixListener;
+
+    // A socket left behind by an earlier run would stop the bind, but anything else at the path
+    // is left alone in case control_socket points somewhere it shouldn't
+    if let Ok(metadata) = fs::symlink_metadata(&path) {
+        if !metadata.file_type().is_socket() {
+            return Err(format!("{} exists and is not a socket", path.display()).into());
+        }
+
+        fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    // Anyone who can connect can control the bot, so only its own user may
+    fs::set_permissions(&path, Permissions::from_mode(0o600))?;
+    tracing::info!("Serving the control socket at {}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let bot = bot.clone();
+        tokio::spawn(async move {
+            if let Err(why) = handle(bot, stream).await {
+                tracing::warn!("Control connection failed: {}", why);
+            }
+        });
+    }
+}
+
+#[cfg(windows)]
+pub async fn serve(bot: Bot, path: PathBuf) -> Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&path)?;
+    tracing::info!("Serving the control pipe at {}", path.display());
+
+    loop {
+        server.connect().await?;
+        // The next client connects to a new instance of the pipe
+        let stream = std::mem::replace(&mut server, ServerOptions::new().create(&path)?);
+        let bot = bot.clone();
+        tokio::spawn(async move {
+            if let Err(why) = handle(bot, stream).await {
+                tracing::warn!("Control connection failed: {}", why);
+            }
+        });
+    }
+}
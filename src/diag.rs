@@ -0,0 +1,178 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    io::{Cursor, Write},
+    time::Instant,
+};
+
+use taskinator_communicator::game::State;
+use toml::Value;
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::Result;
+
+const MAX_STATES: usize = 20;
+
+// Settings whose values are secret, rather than just identifying
+const SECRET_KEYS: &[&str] = &["token", "telemetry_endpoint", "sentry_dsn"];
+
+// The game states the bot most recently acted on, for working out what it saw when it misbehaved
+#[derive(Default)]
+pub struct StateHistory {
+    states: VecDeque<(Instant, String)>,
+}
+
+impl StateHistory {
+    pub fn record(&mut self, state: Option<&State>) {
+        let state = state.map_or_else(|| "No game".to_owned(), |state| format!("{:#?}", state));
+        self.states.push_back((Instant::now(), state));
+        if self.states.len() > MAX_STATES {
+            self.states.pop_front();
+        }
+    }
+
+    pub fn dump(&self) -> String {
+        let mut dump = String::new();
+        for (at, state) in &self.states {
+            let _ = writeln!(dump, "{}s ago:\n{}\n", at.elapsed().as_secs(), state);
+        }
+
+        dump
+    }
+}
+
+// Strips secrets, and swaps Discord IDs for placeholders that are the same everywhere in the
+// bundle, so what refers to what can still be followed
+#[derive(Default)]
+pub struct Sanitizer {
+    ids: HashMap<String, String>,
+}
+
+impl Sanitizer {
+    pub fn config(&mut self, contents: &str) -> Result<String> {
+        let mut config: Value = toml::from_str(contents)?;
+
+        if let Some(table) = config.as_table_mut() {
+            for key in SECRET_KEYS {
+                if let Some(value) = table.get_mut(*key) {
+                    *value = Value::String("<redacted>".to_owned());
+                }
+            }
+        }
+
+        Ok(self.text(&toml::to_string(&config)?))
+    }
+
+    pub fn text(&mut self, text: &str) -> String {
+        let mut sanitized = String::with_capacity(text.len());
+        let mut digits = String::new();
+
+        for c in text.chars().chain(std::iter::once(' ')) {
+            if c.is_ascii_digit() {
+                digits.push(c);
+                continue;
+            }
+            sanitized.push_str(&self.id(&digits));
+            digits.clear();
+            sanitized.push(c);
+        }
+        sanitized.pop();
+
+        sanitized
+    }
+
+    // Snowflakes are 17 to 20 digits long, shorter numbers are left alone
+    fn id(&mut self, digits: &str) -> String {
+        if !(17..=20).contains(&digits.len()) {
+            return digits.to_owned();
+        }
+
+        let next = self.ids.len() + 1;
+        self.ids
+            .entry(digits.to_owned())
+            .or_insert_with(|| format!("<id {}>", next))
+            .clone()
+    }
+}
+
+pub fn version() -> String {
+    let features = [
+        ("grpc", cfg!(feature = "grpc")),
+        ("otel", cfg!(feature = "otel")),
+        ("sentry", cfg!(feature = "sentry")),
+        ("telemetry", cfg!(feature = "telemetry")),
+        ("updates", cfg!(feature = "updates")),
+        ("voice-status", cfg!(feature = "voice-status")),
+    ]
+    .iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(feature, _)| *feature)
+    .collect::<Vec<_>>();
+
+    format!(
+        "taskinator {}\nfeatures: {}\nos: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        if features.is_empty() {
+            "none".to_owned()
+        } else {
+            features.join(", ")
+        },
+        std::env::consts::OS
+    )
+}
+
+pub fn bundle(files: &[(&str, String)]) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    for (name, contents) in files {
+        zip.start_file(*name, FileOptions::default())?;
+        zip.write_all(contents.as_bytes())?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_get_the_same_placeholder_everywhere() {
+        let mut sanitizer = Sanitizer::default();
+
+        assert_eq!(
+            sanitizer.text("muted 123456789012345678 in 223456789012345678"),
+            "muted <id 1> in <id 2>"
+        );
+        assert_eq!(
+            sanitizer.text("unmuted 123456789012345678"),
+            "unmuted <id 1>"
+        );
+    }
+
+    #[test]
+    fn other_numbers_are_left_alone() {
+        let mut sanitizer = Sanitizer::default();
+
+        assert_eq!(
+            sanitizer.text("took 1500ms at 12:30"),
+            "took 1500ms at 12:30"
+        );
+        assert_eq!(
+            sanitizer.text("1234567890123456 123456789012345678901"),
+            "1234567890123456 123456789012345678901"
+        );
+    }
+
+    #[test]
+    fn secrets_are_taken_out_of_the_config() {
+        let mut sanitizer = Sanitizer::default();
+        let config = sanitizer
+            .config("token = \"secret\"\nliving_channel = \"123456789012345678\"\n")
+            .unwrap();
+
+        assert!(!config.contains("secret"));
+        assert!(config.contains("token = \"<redacted>\""));
+        assert!(config.contains("living_channel = \"<id 1>\""));
+    }
+}
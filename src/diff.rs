@@ -0,0 +1,350 @@
+use taskinator_communicator::game::{MeetingState, Player, State};
+
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    GameStarted,
+    GameEnded,
+    MeetingStarted,
+    MeetingEnded,
+    VotingStarted,
+    PlayerJoined(String),
+    PlayerLeft(String),
+    PlayerDied(String),
+}
+
+pub fn voting(meeting: &MeetingState) -> bool {
+    matches!(meeting, MeetingState::NotVoted | MeetingState::Voted)
+}
+
+fn in_meeting(meeting: &MeetingState) -> bool {
+    matches!(
+        meeting,
+        MeetingState::Discussion
+            | MeetingState::NotVoted
+            | MeetingState::Voted
+            | MeetingState::Results
+    )
+}
+
+// Each player's name, and whether they're dead and an impostor
+type Flags = Vec<(String, bool, bool)>;
+
+fn flags(players: &[Player]) -> Flags {
+    players
+        .iter()
+        .map(|p| (p.name.clone(), p.dead, p.impostor))
+        .collect()
+}
+
+// Within a game the dead stay dead and the impostors don't change, so either happening means a
+// new game has started
+fn restarted(previous: &[(String, bool, bool)], current: &[(String, bool, bool)]) -> bool {
+    current.iter().any(|(name, dead, impostor)| {
+        previous.iter().any(|(before, was_dead, was_impostor)| {
+            before == name && ((*was_dead && !dead) || was_impostor != impostor)
+        })
+    })
+}
+
+fn same_players(previous: &[(String, bool, bool)], current: &[(String, bool, bool)]) -> bool {
+    previous.len() == current.len()
+        && current
+            .iter()
+            .all(|(name, ..)| previous.iter().any(|(before, ..)| before == name))
+}
+
+// Memory reads can briefly show a player as dead, or with the wrong role, so unless the players
+// changed too a new game is only believed once two polls in a row show it
+#[derive(Default)]
+pub struct Differ {
+    // What two polls in a row agreed on
+    settled: Flags,
+    last: Flags,
+    suspected: bool,
+}
+
+impl Differ {
+    pub fn diff(&mut self, previous: Option<&State>, current: Option<&State>) -> Vec<Change> {
+        let now = if let Some(State::InGame { players, .. }) = current {
+            flags(players)
+        } else {
+            *self = Self::default();
+            return diff(previous, current, false);
+        };
+
+        let suspect = restarted(&self.settled, &now);
+        let restart = suspect && (self.suspected || !same_players(&self.last, &now));
+        self.suspected = suspect && !restart;
+        if restart || now == self.last {
+            self.settled.clone_from(&now);
+        }
+        self.last = now;
+
+        diff(previous, current, restart)
+    }
+}
+
+fn started(meeting: &MeetingState) -> Change {
+    // Joining a game mid-meeting, e.g. on startup, goes straight to the meeting
+    if in_meeting(meeting) {
+        Change::MeetingStarted
+    } else {
+        Change::GameStarted
+    }
+}
+
+// The changes between two consecutive updates from the watcher, in the order they should be acted
+// on. A new game that skipped the lobby ends the old game before starting the new one
+fn diff(previous: Option<&State>, current: Option<&State>, restarted: bool) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    match (previous, current) {
+        (
+            Some(State::InGame {
+                players: before,
+                meeting: was,
+            }),
+            Some(State::InGame { players, meeting }),
+        ) => {
+            if restarted {
+                changes.push(Change::GameEnded);
+                changes.push(started(meeting));
+                return changes;
+            }
+
+            for player in players {
+                match before.iter().find(|before| before.name == player.name) {
+                    Some(before) if !before.dead && player.dead => {
+                        changes.push(Change::PlayerDied(player.name.clone()));
+                    }
+                    Some(_) => {}
+                    None => changes.push(Change::PlayerJoined(player.name.clone())),
+                }
+            }
+            for before in before {
+                if !players.iter().any(|player| player.name == before.name) {
+                    changes.push(Change::PlayerLeft(before.name.clone()));
+                }
+            }
+
+            match (in_meeting(was), in_meeting(meeting)) {
+                (false, true) => changes.push(Change::MeetingStarted),
+                (true, false) => changes.push(Change::MeetingEnded),
+                _ => {}
+            }
+            if voting(meeting) && !voting(was) {
+                changes.push(Change::VotingStarted);
+            }
+        }
+        (_, Some(State::InGame { meeting, .. })) => changes.push(started(meeting)),
+        (Some(State::InGame { .. }), _) => changes.push(Change::GameEnded),
+        _ => {}
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(name: &str, dead: bool, impostor: bool) -> Player {
+        Player {
+            name: name.to_owned(),
+            dead,
+            impostor,
+        }
+    }
+
+    fn in_game(players: Vec<Player>, meeting: MeetingState) -> State {
+        State::InGame { players, meeting }
+    }
+
+    fn flag_list(players: &[(&str, bool, bool)]) -> Flags {
+        players
+            .iter()
+            .map(|&(name, dead, impostor)| (name.to_owned(), dead, impostor))
+            .collect()
+    }
+
+    #[test]
+    fn restarted_when_the_dead_come_back() {
+        let before = flag_list(&[("Red", true, false), ("Blue", false, true)]);
+        let after = flag_list(&[("Red", false, false), ("Blue", false, true)]);
+
+        assert!(restarted(&before, &after));
+    }
+
+    #[test]
+    fn restarted_when_impostors_change() {
+        let before = flag_list(&[("Red", false, false), ("Blue", false, true)]);
+        let after = flag_list(&[("Red", false, true), ("Blue", false, false)]);
+
+        assert!(restarted(&before, &after));
+    }
+
+    #[test]
+    fn not_restarted_by_a_death() {
+        let before = flag_list(&[("Red", false, false), ("Blue", false, true)]);
+        let after = flag_list(&[("Red", true, false), ("Blue", false, true)]);
+
+        assert!(!restarted(&before, &after));
+    }
+
+    #[test]
+    fn not_restarted_by_players_leaving_or_joining() {
+        let before = flag_list(&[("Red", true, false), ("Blue", false, true)]);
+        let after = flag_list(&[("Blue", false, true), ("Green", false, false)]);
+
+        assert!(!restarted(&before, &after));
+    }
+
+    #[test]
+    fn game_start_and_end() {
+        let lobby = State::Lobby {
+            players: vec![player("Red", false, false)],
+        };
+        let game = in_game(vec![player("Red", false, false)], MeetingState::Discussion);
+
+        assert_eq!(
+            diff(Some(&lobby), Some(&game), false),
+            vec![Change::MeetingStarted]
+        );
+        assert_eq!(
+            diff(Some(&game), Some(&lobby), false),
+            vec![Change::GameEnded]
+        );
+        assert_eq!(diff(Some(&game), None, false), vec![Change::GameEnded]);
+    }
+
+    #[test]
+    fn deaths_joins_and_leaves() {
+        let before = in_game(
+            vec![player("Red", false, false), player("Blue", false, true)],
+            MeetingState::Discussion,
+        );
+        let after = in_game(
+            vec![player("Red", true, false), player("Green", false, false)],
+            MeetingState::Discussion,
+        );
+
+        assert_eq!(
+            diff(Some(&before), Some(&after), false),
+            vec![
+                Change::PlayerDied("Red".to_owned()),
+                Change::PlayerJoined("Green".to_owned()),
+                Change::PlayerLeft("Blue".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn voting_started() {
+        let players = vec![player("Red", false, false)];
+        let discussion = in_game(players.clone(), MeetingState::Discussion);
+        let voting = in_game(players, MeetingState::NotVoted);
+
+        assert_eq!(
+            diff(Some(&discussion), Some(&voting), false),
+            vec![Change::VotingStarted]
+        );
+    }
+
+    #[test]
+    fn restart_ends_and_starts_a_game() {
+        let before = in_game(vec![player("Red", true, false)], MeetingState::Discussion);
+        let after = in_game(vec![player("Red", false, false)], MeetingState::Discussion);
+
+        assert_eq!(
+            diff(Some(&before), Some(&after), true),
+            vec![Change::GameEnded, Change::MeetingStarted]
+        );
+    }
+
+    // Feeds states through a differ as the bot would, returning the changes for each
+    fn run(states: &[State]) -> Vec<Vec<Change>> {
+        let mut differ = Differ::default();
+        let mut previous = None;
+
+        states
+            .iter()
+            .map(|state| {
+                let changes = differ.diff(previous, Some(state));
+                previous = Some(state);
+                changes
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_flickering_death_is_not_a_new_game() {
+        let alive = in_game(
+            vec![player("Red", false, false), player("Blue", false, true)],
+            MeetingState::Discussion,
+        );
+        let flicker = in_game(
+            vec![player("Red", true, false), player("Blue", false, true)],
+            MeetingState::Discussion,
+        );
+
+        let changes = run(&[alive.clone(), alive.clone(), flicker, alive.clone(), alive]);
+
+        assert!(changes
+            .iter()
+            .flatten()
+            .all(|change| *change != Change::GameEnded));
+    }
+
+    #[test]
+    fn a_flickering_impostor_is_not_a_new_game() {
+        let game = in_game(
+            vec![player("Red", false, false), player("Blue", false, true)],
+            MeetingState::Discussion,
+        );
+        let flicker = in_game(
+            vec![player("Red", false, true), player("Blue", false, true)],
+            MeetingState::Discussion,
+        );
+
+        let changes = run(&[game.clone(), game.clone(), flicker, game]);
+
+        assert!(changes
+            .iter()
+            .flatten()
+            .all(|change| *change != Change::GameEnded));
+    }
+
+    #[test]
+    fn a_new_game_is_believed_once_seen_twice() {
+        let old = in_game(
+            vec![player("Red", true, false), player("Blue", false, true)],
+            MeetingState::Discussion,
+        );
+        let new = in_game(
+            vec![player("Red", false, true), player("Blue", false, false)],
+            MeetingState::Discussion,
+        );
+
+        let changes = run(&[old.clone(), old, new.clone(), new.clone(), new]);
+
+        assert!(!changes[2].contains(&Change::GameEnded));
+        assert_eq!(changes[3], vec![Change::GameEnded, Change::MeetingStarted]);
+        assert!(changes[4].is_empty());
+    }
+
+    #[test]
+    fn a_new_game_with_other_players_is_believed_at_once() {
+        let old = in_game(
+            vec![player("Red", true, false), player("Blue", false, true)],
+            MeetingState::Discussion,
+        );
+        let new = in_game(
+            vec![player("Red", false, false), player("Green", false, true)],
+            MeetingState::Discussion,
+        );
+
+        let changes = run(&[old.clone(), old, new]);
+
+        assert_eq!(changes[2], vec![Change::GameEnded, Change::MeetingStarted]);
+    }
+}
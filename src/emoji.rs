@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use twilight_http::request::channel::reaction::RequestReactionType;
+use twilight_model::id::EmojiId;
+
+// The things the bot shows with an emoji, named as they're written in the config and ~emoji
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Alive,
+    Dead,
+    CrewWin,
+    ImpostorsWin,
+    UnknownCommand,
+    Denied,
+}
+
+impl Status {
+    pub const ALL: [Status; 6] = [
+        Status::Alive,
+        Status::Dead,
+        Status::CrewWin,
+        Status::ImpostorsWin,
+        Status::UnknownCommand,
+        Status::Denied,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Alive => "alive",
+            Status::Dead => "dead",
+            Status::CrewWin => "crew-win",
+            Status::ImpostorsWin => "impostors-win",
+            Status::UnknownCommand => "unknown-command",
+            Status::Denied => "denied",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|status| status.name() == name)
+    }
+
+    fn default_emoji(self) -> Emoji {
+        Emoji::Unicode(
+            match self {
+                Status::Alive => "🟢",
+                Status::Dead => "💀",
+                Status::CrewWin => "🔵",
+                Status::ImpostorsWin => "🔴",
+                Status::UnknownCommand => "❓",
+                Status::Denied => "🔒",
+            }
+            .to_owned(),
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Emoji {
+    Unicode(String),
+    // A server's own emoji, written as Discord does in messages, e.g. <:dead:123456789012345678>
+    Custom {
+        animated: bool,
+        name: String,
+        id: EmojiId,
+    },
+}
+
+impl Emoji {
+    pub fn reaction(&self) -> RequestReactionType {
+        match self {
+            Emoji::Unicode(emoji) => RequestReactionType::Unicode {
+                name: emoji.clone(),
+            },
+            Emoji::Custom { name, id, .. } => RequestReactionType::Custom {
+                id: *id,
+                name: Some(name.clone()),
+            },
+        }
+    }
+
+    pub fn custom_id(&self) -> Option<EmojiId> {
+        match self {
+            Emoji::Unicode(_) => None,
+            Emoji::Custom { id, .. } => Some(*id),
+        }
+    }
+}
+
+impl FromStr for Emoji {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const INVALID: &str = "must be an emoji, or a server emoji like <:name:123456789012345678>";
+
+        let custom = match s.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+            Some(custom) => custom,
+            // Anything that isn't plain text is trusted to be an emoji, Discord has the final say
+            None if !s.is_empty() && !s.is_ascii() => return Ok(Emoji::Unicode(s.to_owned())),
+            None => return Err(INVALID),
+        };
+
+        match custom.split(':').collect::<Vec<_>>()[..] {
+            [animated @ ("" | "a"), name, id] if !name.is_empty() => Ok(Emoji::Custom {
+                animated: animated == "a",
+                name: name.to_owned(),
+                id: EmojiId(id.parse().map_err(|_| INVALID)?),
+            }),
+            _ => Err(INVALID),
+        }
+    }
+}
+
+impl fmt::Display for Emoji {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Emoji::Unicode(emoji) => f.write_str(emoji),
+            Emoji::Custom { animated, name, id } => {
+                write!(f, "<{}:{}:{}>", if *animated { "a" } else { "" }, name, id)
+            }
+        }
+    }
+}
+
+// Emoji chosen in the config, falling back to the defaults. Entries are validated with the
+// config, so any that don't parse here have already been reported
+#[derive(Clone, Default)]
+pub struct EmojiSet(BTreeMap<String, Emoji>);
+
+impl EmojiSet {
+    pub fn new(emoji: &BTreeMap<String, String>) -> Self {
+        Self(
+            emoji
+                .iter()
+                .filter_map(|(status, emoji)| Some((status.clone(), emoji.parse().ok()?)))
+                .collect(),
+        )
+    }
+
+    // Overrides take precedence, e.g. a server's own choices from ~emoji
+    pub fn get(&self, status: Status, overrides: &BTreeMap<String, String>) -> Emoji {
+        overrides
+            .get(status.name())
+            .and_then(|emoji| emoji.parse().ok())
+            .or_else(|| self.0.get(status.name()).cloned())
+            .unwrap_or_else(|| status.default_emoji())
+    }
+
+    pub fn custom_ids(&self) -> Vec<EmojiId> {
+        self.0.values().filter_map(Emoji::custom_id).collect()
+    }
+}
@@ -0,0 +1,79 @@
+use std::net::SocketAddr;
+
+use tonic::{transport::Server, Request, Response, Status};
+
+use crate::bot::Bot;
+
+#[allow(clippy::pedantic)]
+mod proto {
+    tonic::include_proto!("taskinator");
+}
+
+use proto::{
+    control_server::{Control, ControlServer},
+    Empty, SetPausedRequest,
+};
+
+struct ControlService {
+    bot: Bot,
+}
+
+impl ControlService {
+    fn status(&self) -> proto::Status {
+        proto::Status {
+            phase: self.bot.phase().to_owned(),
+            paused: self.bot.is_paused(),
+            players: self
+                .bot
+                .roster()
+                .into_iter()
+                .map(|entry| proto::Player {
+                    name: entry.name,
+                    dead: entry.dead,
+                    discord_user_id: entry.user_id.map_or(0, |id| id.0),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Control for ControlService {
+    async fn get_status(&self, _: Request<Empty>) -> Result<Response<proto::Status>, Status> {
+        Ok(Response::new(self.status()))
+    }
+
+    async fn set_paused(
+        &self,
+        request: Request<SetPausedRequest>,
+    ) -> Result<Response<proto::Status>, Status> {
+        self.bot.set_paused(request.into_inner().paused);
+
+        Ok(Response::new(self.status()))
+    }
+
+    async fn start_game(&self, _: Request<Empty>) -> Result<Response<proto::Status>, Status> {
+        if self.bot.force_start_game().await {
+            Ok(Response::new(self.status()))
+        } else {
+            Err(Status::failed_precondition("There is no game in progress"))
+        }
+    }
+
+    async fn end_game(&self, _: Request<Empty>) -> Result<Response<proto::Status>, Status> {
+        self.bot.force_end_game().await;
+
+        Ok(Response::new(self.status()))
+    }
+}
+
+pub async fn serve(bot: Bot, address: SocketAddr) -> crate::Result<()> {
+    tracing::info!("Serving the gRPC API on {}", address);
+
+    Server::builder()
+        .add_service(ControlServer::new(ControlService { bot }))
+        .serve(address)
+        .await?;
+
+    Ok(())
+}
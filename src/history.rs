@@ -0,0 +1,99 @@
+use std::{collections::VecDeque, fmt::Write as _, time::UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use twilight_model::application::component::{button::ButtonStyle, ActionRow, Button, Component};
+
+use crate::summary::{GameResult, Team};
+
+const MAX_GAMES: usize = 50;
+const PAGE_SIZE: usize = 5;
+
+// Buttons carry which way they go and the page they go to after this prefix, the direction keeps
+// their IDs apart when both are disabled
+pub const PAGE_ID: &str = "taskinator-history-";
+
+// The most recent games, newest first, kept in the store
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct GameHistory {
+    games: VecDeque<GameResult>,
+}
+
+impl GameHistory {
+    pub fn record(&mut self, game: GameResult) {
+        self.games.push_front(game);
+        self.games.truncate(MAX_GAMES);
+    }
+
+    pub fn pages(&self) -> usize {
+        (self.games.len() + PAGE_SIZE - 1) / PAGE_SIZE
+    }
+
+    pub fn page(&self, page: usize) -> String {
+        let mut listing = String::new();
+
+        for game in self.games.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+            let impostors = game
+                .players
+                .iter()
+                .filter(|p| p.impostor)
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>();
+
+            let _ = writeln!(
+                listing,
+                "<t:{}:t> {}m {}s, {} meetings, {}, impostors: {}",
+                game.ended
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_secs()),
+                game.duration.as_secs() / 60,
+                game.duration.as_secs() % 60,
+                game.meetings,
+                match game.winner {
+                    Some(Team::Crew) => "crew won",
+                    Some(Team::Impostors) => "impostors won",
+                    None => "winner unknown",
+                },
+                impostors.join(", ")
+            );
+        }
+
+        let _ = write!(listing, "Page {} of {}", page + 1, self.pages());
+
+        listing
+    }
+}
+
+pub fn page_of(custom_id: &str) -> Option<usize> {
+    let (_, page) = custom_id.strip_prefix(PAGE_ID)?.split_once('-')?;
+    page.parse().ok()
+}
+
+pub fn buttons(page: usize, pages: usize) -> Vec<Component> {
+    let button = |label: &str, direction: &str, target: Option<usize>| {
+        Component::Button(Button {
+            custom_id: Some(format!(
+                "{}{}-{}",
+                PAGE_ID,
+                direction,
+                target.unwrap_or(page)
+            )),
+            disabled: target.is_none(),
+            emoji: None,
+            label: Some(label.to_owned()),
+            style: ButtonStyle::Secondary,
+            url: None,
+        })
+    };
+
+    vec![Component::ActionRow(ActionRow {
+        components: vec![
+            button("Newer", "newer", page.checked_sub(1)),
+            button(
+                "Older",
+                "older",
+                Some(page + 1).filter(|&older| older < pages),
+            ),
+        ],
+    })]
+}
@@ -0,0 +1,302 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
+use parking_lot::Mutex;
+use tokio::time::sleep;
+use tracing::Instrument;
+use twilight_http::{
+    error::{Error as HttpError, ErrorType},
+    request::AuditLogReason,
+    Client,
+};
+use twilight_mention::Mention;
+use twilight_model::id::{ChannelId, GuildId, UserId};
+
+const MAX_ATTEMPTS: u32 = 3;
+const BACKOFF: Duration = Duration::from_millis(500);
+const HISTORY_LEN: usize = 10;
+// Requests failing in a row on Discord's side before it's treated as an outage
+const OUTAGE_FAILURES: u64 = 5;
+
+// A voice change for one member, kept as data so a failed request can be rebuilt and retried
+#[derive(Clone, Copy, Debug)]
+pub struct MemberUpdate {
+    guild_id: GuildId,
+    user_id: UserId,
+    // Shown in the server's audit log so admins can tell the bot's actions from moderators'
+    reason: &'static str,
+    mute: Option<bool>,
+    deaf: Option<bool>,
+    channel_id: Option<ChannelId>,
+}
+
+impl MemberUpdate {
+    pub fn new(guild_id: GuildId, user_id: UserId, reason: &'static str) -> Self {
+        Self {
+            guild_id,
+            user_id,
+            reason,
+            mute: None,
+            deaf: None,
+            channel_id: None,
+        }
+    }
+
+    pub fn member(&self) -> (GuildId, UserId) {
+        (self.guild_id, self.user_id)
+    }
+
+    pub fn mute(self, mute: bool) -> Self {
+        Self {
+            mute: Some(mute),
+            ..self
+        }
+    }
+
+    pub fn deaf(self, deaf: bool) -> Self {
+        Self {
+            deaf: Some(deaf),
+            ..self
+        }
+    }
+
+    pub fn channel_id(self, channel_id: ChannelId) -> Self {
+        Self {
+            channel_id: Some(channel_id),
+            ..self
+        }
+    }
+
+    // Whether a member's voice state shows this update, only what the update changes is compared
+    pub fn settled(&self, mute: bool, deaf: bool, channel_id: Option<ChannelId>) -> bool {
+        self.mute.map_or(true, |m| m == mute)
+            && self.deaf.map_or(true, |d| d == deaf)
+            && self.channel_id.map_or(true, |c| Some(c) == channel_id)
+    }
+}
+
+impl fmt::Display for MemberUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.user_id.mention())?;
+
+        match self.mute {
+            Some(true) => write!(f, " mute")?,
+            Some(false) => write!(f, " unmute")?,
+            None => {}
+        }
+
+        match self.deaf {
+            Some(true) => write!(f, " deafen")?,
+            Some(false) => write!(f, " undeafen")?,
+            None => {}
+        }
+
+        if let Some(channel_id) = self.channel_id {
+            write!(f, " move to {}", channel_id.mention())?;
+        }
+
+        write!(f, " ({})", self.reason)
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    failures: AtomicU64,
+    // Reset by any successful request
+    server_failures: AtomicU64,
+}
+
+#[derive(Clone, Copy)]
+pub struct Change {
+    pub at: SystemTime,
+    pub update: MemberUpdate,
+    pub succeeded: bool,
+}
+
+pub struct Stats {
+    pub requests: u64,
+    pub retries: u64,
+    pub failures: u64,
+}
+
+// Shared by everything that talks to Discord; requests sent through `send` are traced, counted
+// and retried with backoff, everything else goes straight to the wrapped client
+#[derive(Clone)]
+pub struct Http {
+    client: Client,
+    counters: Arc<Counters>,
+    // The latest voice changes to every member the bot has changed since it started
+    history: Arc<Mutex<HashMap<(GuildId, UserId), VecDeque<Change>>>>,
+}
+
+impl Http {
+    pub fn new(token: &str) -> Self {
+        Self {
+            client: Client::new(token),
+            counters: Arc::new(Counters::default()),
+            history: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn send<F, Fut, T>(&self, route: &'static str, request: F) -> Result<T, HttpError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, HttpError>>,
+    {
+        let span = tracing::debug_span!("discord_request", route);
+
+        async {
+            let mut attempt = 1;
+
+            loop {
+                self.counters.requests.fetch_add(1, Ordering::Relaxed);
+
+                match request().await {
+                    Ok(response) => {
+                        self.counters.server_failures.store(0, Ordering::Relaxed);
+                        return Ok(response);
+                    }
+                    // Retrying won't change the answer to a request Discord refused, e.g. for want of
+                    // a permission
+                    Err(why) if attempt < MAX_ATTEMPTS && is_transient(&why) => {
+                        tracing::warn!("Attempt {} failed, retrying: {}", attempt, why);
+                        self.counters.retries.fetch_add(1, Ordering::Relaxed);
+                        sleep(BACKOFF * attempt).await;
+                        attempt += 1;
+                    }
+                    Err(why) => {
+                        self.counters.failures.fetch_add(1, Ordering::Relaxed);
+                        if is_server_error(&why) {
+                            self.counters
+                                .server_failures
+                                .fetch_add(1, Ordering::Relaxed);
+                        }
+                        return Err(why);
+                    }
+                }
+            }
+        }
+        .instrument(span)
+        .await
+    }
+
+    pub async fn update_member(&self, update: MemberUpdate) -> Result<(), HttpError> {
+        let result = self
+            .send("update_guild_member", || {
+                let request = self
+                    .client
+                    .update_guild_member(update.guild_id, update.user_id)
+                    .reason(update.reason)
+                    .expect("audit log reasons are short constants");
+                let request = match update.mute {
+                    Some(mute) => request.mute(mute),
+                    None => request,
+                };
+                let request = match update.deaf {
+                    Some(deaf) => request.deaf(deaf),
+                    None => request,
+                };
+
+                match update.channel_id {
+                    Some(channel_id) => request.channel_id(channel_id),
+                    None => request,
+                }
+            })
+            .await
+            .map(drop);
+
+        let mut history = self.history.lock();
+        let changes = history
+            .entry((update.guild_id, update.user_id))
+            .or_default();
+        changes.push_front(Change {
+            at: SystemTime::now(),
+            update,
+            succeeded: result.is_ok(),
+        });
+        changes.truncate(HISTORY_LEN);
+
+        result
+    }
+
+    pub fn touched(&self) -> Vec<(GuildId, UserId)> {
+        self.history.lock().keys().copied().collect()
+    }
+
+    // Whether the last mute or unmute the bot made for the member was a mute, so members muted by
+    // a moderator since aren't taken for the bot's doing
+    pub fn muted_by_bot(&self, guild_id: GuildId, user_id: UserId) -> bool {
+        self.history
+            .lock()
+            .get(&(guild_id, user_id))
+            .and_then(|changes| {
+                changes
+                    .iter()
+                    .find(|change| change.succeeded && change.update.mute.is_some())
+            })
+            .map_or(false, |change| change.update.mute == Some(true))
+    }
+
+    // Newest first
+    pub fn member_history(&self, guild_id: GuildId, user_id: UserId) -> Vec<Change> {
+        self.history
+            .lock()
+            .get(&(guild_id, user_id))
+            .map(|changes| changes.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn discord_down(&self) -> bool {
+        self.counters.server_failures.load(Ordering::Relaxed) >= OUTAGE_FAILURES
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            requests: self.counters.requests.load(Ordering::Relaxed),
+            retries: self.counters.retries.load(Ordering::Relaxed),
+            failures: self.counters.failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Deref for Http {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+// Discord's answer to DMs for members who have blocked the bot or only accept DMs from friends
+pub fn is_forbidden(why: &HttpError) -> bool {
+    matches!(why.kind(), ErrorType::Response { status, .. } if status.as_u16() == 403)
+}
+
+pub fn is_not_found(why: &HttpError) -> bool {
+    matches!(why.kind(), ErrorType::Response { status, .. } if status.as_u16() == 404)
+}
+
+fn is_transient(why: &HttpError) -> bool {
+    is_server_error(why)
+        || matches!(why.kind(), ErrorType::Response { status, .. } if status.as_u16() == 429)
+}
+
+// Failures that are Discord's fault rather than the request's, e.g. missing permissions
+fn is_server_error(why: &HttpError) -> bool {
+    match why.kind() {
+        ErrorType::Response { status, .. } => status.is_server_error(),
+        ErrorType::ServiceUnavailable { .. } | ErrorType::RequestTimedOut => true,
+        _ => false,
+    }
+}
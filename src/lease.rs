@@ -0,0 +1,191 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    process,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use twilight_model::id::GuildId;
+
+use crate::Result;
+
+// Leases are renewed on every reconcile, one this old belongs to an instance that has stopped
+const TTL: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize, Serialize)]
+struct Entry {
+    instance: String,
+    renewed: u64,
+}
+
+// Which instance is automating each server, so a second one started by mistake (e.g. the old
+// process wasn't killed) stands down rather than fighting it over every member
+pub struct Lease {
+    path: PathBuf,
+    instance: String,
+}
+
+impl Lease {
+    pub fn new(path: PathBuf) -> Self {
+        // Process IDs are reused, the start time tells instances apart
+        let instance = format!("{}-{}", process::id(), now());
+
+        Self { path, instance }
+    }
+
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    // Claims the server for this instance, unless another renewed its lease recently and force
+    // isn't set, in which case that instance is returned
+    pub fn claim(&self, guild_id: GuildId, force: bool) -> Result<Option<String>> {
+        let mut leases = self.read()?;
+
+        if let Some(entry) = leases.get(&guild_id.to_string()) {
+            let fresh = now().saturating_sub(entry.renewed) < TTL.as_secs();
+            if entry.instance != self.instance && fresh && !force {
+                return Ok(Some(entry.instance.clone()));
+            }
+        }
+
+        leases.insert(
+            guild_id.to_string(),
+            Entry {
+                instance: self.instance.clone(),
+                renewed: now(),
+            },
+        );
+        self.write(&leases)?;
+
+        Ok(None)
+    }
+
+    pub fn release(&self, guild_id: GuildId) -> Result<()> {
+        let mut leases = self.read()?;
+
+        let key = guild_id.to_string();
+        if leases
+            .get(&key)
+            .map_or(false, |entry| entry.instance == self.instance)
+        {
+            leases.remove(&key);
+            self.write(&leases)?;
+        }
+
+        Ok(())
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, Entry>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        Ok(toml::from_str(&fs::read_to_string(&self.path)?)?)
+    }
+
+    fn write(&self, leases: &BTreeMap<String, Entry>) -> Result<()> {
+        fs::write(&self.path, toml::to_string(leases)?)?;
+
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    const GUILD: GuildId = GuildId(1);
+
+    // Instances made in the same second would share a name, so tests name their own
+    fn lease(path: &Path, instance: &str) -> Lease {
+        Lease {
+            path: path.to_owned(),
+            instance: instance.to_owned(),
+        }
+    }
+
+    fn path(test: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("taskinator-lease-{}-{}.toml", process::id(), test));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn a_fresh_lease_keeps_other_instances_out() {
+        let path = path("fresh");
+        let (first, second) = (lease(&path, "first"), lease(&path, "second"));
+
+        assert_eq!(first.claim(GUILD, false).unwrap(), None);
+        assert_eq!(first.claim(GUILD, false).unwrap(), None);
+        assert_eq!(
+            second.claim(GUILD, false).unwrap(),
+            Some("first".to_owned())
+        );
+        assert_eq!(first.claim(GuildId(2), false).unwrap(), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn force_takes_over_a_fresh_lease() {
+        let path = path("force");
+        let (first, second) = (lease(&path, "first"), lease(&path, "second"));
+
+        first.claim(GUILD, false).unwrap();
+        assert_eq!(second.claim(GUILD, true).unwrap(), None);
+        assert_eq!(
+            first.claim(GUILD, false).unwrap(),
+            Some("second".to_owned())
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_stale_lease_can_be_claimed() {
+        let path = path("stale");
+        let mut leases = BTreeMap::new();
+        leases.insert(
+            GUILD.to_string(),
+            Entry {
+                instance: "stopped".to_owned(),
+                renewed: now() - TTL.as_secs() - 1,
+            },
+        );
+        lease(&path, "stopped").write(&leases).unwrap();
+
+        assert_eq!(lease(&path, "next").claim(GUILD, false).unwrap(), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn only_the_holder_releases_a_lease() {
+        let path = path("release");
+        let (first, second) = (lease(&path, "first"), lease(&path, "second"));
+
+        first.claim(GUILD, false).unwrap();
+        second.release(GUILD).unwrap();
+        assert_eq!(
+            second.claim(GUILD, false).unwrap(),
+            Some("first".to_owned())
+        );
+
+        first.release(GUILD).unwrap();
+        assert_eq!(second.claim(GUILD, false).unwrap(), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write as _},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::layer::{Context, Layer};
+
+const MAX_RECORDS: usize = 1000;
+
+struct Record {
+    at: Instant,
+    level: Level,
+    target: String,
+    message: String,
+}
+
+// Keeps the most recent log records in memory, so owners can read them with ~logs without
+// access to the host
+#[derive(Clone, Default)]
+pub struct LogBuffer {
+    records: Arc<Mutex<VecDeque<Record>>>,
+}
+
+impl LogBuffer {
+    // Oldest first, formatted one per line
+    pub fn tail(&self, level: Level, within: Duration) -> Vec<String> {
+        self.records
+            .lock()
+            .iter()
+            .filter(|record| record.level <= level && record.at.elapsed() <= within)
+            .map(|record| {
+                format!(
+                    "{:>4}s ago {:>5} {}: {}",
+                    record.at.elapsed().as_secs(),
+                    record.level,
+                    record.target,
+                    record.message
+                )
+            })
+            .collect()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogBuffer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = String::new();
+        event.record(&mut Message(&mut message));
+
+        let metadata = event.metadata();
+        let mut records = self.records.lock();
+        records.push_back(Record {
+            at: Instant::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_owned(),
+            message,
+        });
+        if records.len() > MAX_RECORDS {
+            records.pop_front();
+        }
+    }
+}
+
+struct Message<'a>(&'a mut String);
+
+impl Visit for Message<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
@@ -7,17 +7,54 @@
     warnings
 )]
 
+mod announcer;
 mod bot;
 mod config;
+mod confirm;
+mod control;
+mod diag;
+mod diff;
+mod emoji;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod history;
+mod http;
+mod lease;
+mod logs;
+mod matching;
+mod panel;
+mod plan;
+mod reporting;
+mod rules;
+mod session;
+mod signals;
+mod source;
+mod store;
+mod summary;
+mod supervisor;
+mod teams;
+mod telemetry;
+mod transcript;
+#[cfg(feature = "updates")]
+mod updates;
 mod utils;
+#[cfg(feature = "voice-status")]
+mod voice_status;
+mod welcome;
 
-use std::time::Duration;
+use crate::{
+    bot::Bot,
+    config::Config,
+    logs::LogBuffer,
+    source::{Multiplexer, SourceConfig},
+    supervisor::{Policy, Supervisor},
+};
 
-use crate::bot::Bot;
+use std::sync::Arc;
 
-use sysinfo::{ProcessExt, RefreshKind, System, SystemExt};
-use taskinator_communicator::game::{Game, State};
-use tokio::{runtime, sync::watch, task::JoinHandle, time::sleep};
+use tokio::{runtime, sync::watch};
+
+pub const CONFIG_PATH: &str = "./Config.toml";
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
@@ -30,109 +67,151 @@ fn main() -> Result<()> {
         .block_on(async { bot_main().await })
 }
 
+const LOG_FILTER: &str = "taskinator=info,taskinator_communicator=info,warn";
+
+#[cfg(feature = "otel")]
+fn init_tracing() -> Result<LogBuffer> {
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+    // The collector is configured with the standard OTEL_EXPORTER_OTLP_* environment variables
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+        .install_batch(opentelemetry::runtime::Tokio)?;
+
+    let logs = LogBuffer::default();
+    tracing_subscriber::registry()
+        .with(EnvFilter::new(LOG_FILTER))
+        .with(tracing_subscriber::fmt::layer())
+        .with(logs.clone())
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .init();
+
+    Ok(logs)
+}
+
+#[cfg(not(feature = "otel"))]
+#[allow(clippy::unnecessary_wraps)]
+fn init_tracing() -> Result<LogBuffer> {
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+    let logs = LogBuffer::default();
+    tracing_subscriber::registry()
+        .with(EnvFilter::new(LOG_FILTER))
+        .with(tracing_subscriber::fmt::layer())
+        .with(logs.clone())
+        .init();
+
+    Ok(logs)
+}
+
 async fn bot_main() -> Result<()> {
     // Setup
-    tracing_subscriber::fmt()
-        .with_env_filter("taskinator=info,taskinator_communicator=info,warn")
-        .init();
+    let logs = init_tracing()?;
+
+    let config = match Config::from_file(CONFIG_PATH).await {
+        Ok(config) => config,
+        Err(why) => {
+            tracing::error!("Failed to read the config file. Aborting!");
+            tracing::error!("{}", why);
+            return Err(why);
+        }
+    };
+
+    let _reporting = reporting::init(config.sentry_dsn.as_deref());
+
+    let (supervisor, reports) = Supervisor::new();
 
     // Start Among Us watcher task
     let (tx, rx) = watch::channel(None);
-    let _au_watcher: JoinHandle<Result<()>> = tokio::spawn(async move {
-        const CONN_RETRY_DELAY: u64 = 5;
-        const POLLING_DELAY: u64 = 2;
-        const MAX_CONSEC_FAILS: u64 = 3;
-
-        let among_us_pid = {
-            let mut system = System::new_with_specifics(RefreshKind::new().with_processes());
-
-            loop {
-                system.refresh_processes();
-
-                if let Some(among_us_proc) = system.get_process_by_name("Among Us.exe").first() {
-                    break among_us_proc.pid();
-                }
-
-                tracing::warn!("Could not find Among Us process... That's a bit sus.");
-                tracing::warn!("Will retry in {} seconds", CONN_RETRY_DELAY);
-
-                sleep(Duration::from_secs(CONN_RETRY_DELAY)).await;
-            }
-        };
-
-        tracing::info!("Among Us process found! PID: {}", among_us_pid);
-
-        let among_us = match Game::from_pid(among_us_pid) {
-            Ok(game) => game,
-            Err(why) => {
-                tracing::error!(
-                    "Opening a connection to the game failed, \
-                                make sure you have sufficient permissions"
-                );
-                return Err(why);
-            }
-        };
-
-        tracing::info!("Established connection to Among Us");
-
-        let mut failure_count = 0;
-        loop {
-            match among_us.state() {
-                Ok(state) => {
-                    if let State::InGame { players, .. } = &state {
-                        let dead_players = players
-                            .iter()
-                            .filter(|p| p.dead)
-                            .map(|p| &p.name)
-                            .collect::<Vec<_>>();
-                        let impostors = players
-                            .iter()
-                            .filter(|p| p.impostor && !p.dead)
-                            .map(|p| &p.name)
-                            .collect::<Vec<_>>();
-                        tracing::debug!("Impostors:\t{:?}", impostors);
-                        if !dead_players.is_empty() {
-                            tracing::debug!("Dead:\t\t{:?}", dead_players);
-                        }
-                    }
-                    tracing::trace!("{:?}", state);
-                    failure_count = 0;
-                    tx.send(Some(state))?;
-                }
-                Err(why) => {
-                    if failure_count < MAX_CONSEC_FAILS {
-                        // If failure count has not reached max, increment but DO NOT update the
-                        // channel
-                        failure_count += 1;
-                        tracing::warn!(
-                            "An error occurred reading Among Us' state ({}/{}). \
-                            This can happen when the game is starting or changing level.",
-                            failure_count,
-                            MAX_CONSEC_FAILS,
-                        );
-                        tracing::warn!("{}", why);
-                    } else {
-                        // At max failure count, log an error and set the channel to None to signal
-                        // no running game
-                        tracing::error!(
-                            "Failed to read Among Us' state again. \
-                            Retries exhausted, has the game closed?"
-                        );
-                        tracing::error!("{}", why);
-                        tx.send(None)?;
-                        return Err(why);
-                    }
-                }
-            }
-            sleep(Duration::from_secs(POLLING_DELAY)).await;
-        }
+    let tx = Arc::new(tx);
+    let sources = config.sources.clone();
+    supervisor.spawn("game watcher", Policy::Essential, move || {
+        Multiplexer::new(sources.iter().cloned().map(SourceConfig::build).collect()).run(tx.clone())
     });
 
     // Setup bot
     tracing::info!("Constructing bot instance from config");
-    let (mut bot, events) = Bot::builder("./Config.toml").build(rx).await?;
-
-    bot.start(events).await?;
+    let (mut bot, events) = Bot::builder(&config)
+        .build(rx, supervisor.clone(), logs)
+        .await?;
+
+    #[cfg(feature = "grpc")]
+    if let Some(address) = config.grpc_address {
+        let bot = bot.clone();
+        supervisor.spawn("gRPC API", Policy::Restart, move || {
+            grpc::serve(bot.clone(), address)
+        });
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    if config.grpc_address.is_some() {
+        tracing::warn!("grpc_address is set, but this build does not include the gRPC API");
+    }
+
+    if let Some(path) = config.control_socket.clone() {
+        let bot = bot.clone();
+        supervisor.spawn("control socket", Policy::Restart, move || {
+            control::serve(bot.clone(), path.clone())
+        });
+    }
+
+    #[cfg(feature = "telemetry")]
+    if let Some(endpoint) = config.telemetry_endpoint.clone() {
+        let telemetry = bot.telemetry();
+        supervisor.spawn("telemetry", Policy::Restart, move || {
+            telemetry::report(telemetry.clone(), endpoint.clone())
+        });
+    }
+
+    #[cfg(not(feature = "telemetry"))]
+    if config.telemetry_endpoint.is_some() {
+        tracing::warn!("telemetry_endpoint is set, but this build does not include telemetry");
+    }
+
+    #[cfg(feature = "updates")]
+    if config.check_for_updates {
+        let bot = bot.clone();
+        supervisor.spawn("update check", Policy::Restart, move || {
+            updates::check(bot.clone())
+        });
+    }
+
+    #[cfg(not(feature = "updates"))]
+    if config.check_for_updates {
+        tracing::warn!(
+            "check_for_updates is set, but this build does not include the update check"
+        );
+    }
+
+    #[cfg(feature = "voice-status")]
+    if config.voice_status {
+        let bot = bot.clone();
+        let token = config.token.clone();
+        supervisor.spawn("voice channel status", Policy::Restart, move || {
+            voice_status::show(bot.clone(), token.clone())
+        });
+    }
+
+    #[cfg(not(feature = "voice-status"))]
+    if config.voice_status {
+        tracing::warn!(
+            "voice_status is set, but this build does not include the voice channel status"
+        );
+    }
+
+    #[cfg(unix)]
+    {
+        let bot = bot.clone();
+        supervisor.spawn("reload signal", Policy::Restart, move || {
+            signals::reload_on_hangup(bot.clone())
+        });
+    }
+
+    bot.start(events, reports).await?;
+
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
 
     Ok(())
 }
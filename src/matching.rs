@@ -0,0 +1,172 @@
+use serde::Deserialize;
+
+use crate::utils::{distance, normalized_name};
+
+// One way of deciding whether a member's in game name belongs to a player. Members are matched by
+// the first strategy, in the configured order, that finds them a player
+pub trait Matcher: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn matches(&self, ign: &str, player: &str) -> bool;
+}
+
+pub struct Exact;
+
+impl Matcher for Exact {
+    fn name(&self) -> &'static str {
+        "exact"
+    }
+
+    fn matches(&self, ign: &str, player: &str) -> bool {
+        ign == player
+    }
+}
+
+// See utils::normalized_name
+pub struct Normalized;
+
+impl Matcher for Normalized {
+    fn name(&self) -> &'static str {
+        "normalized"
+    }
+
+    fn matches(&self, ign: &str, player: &str) -> bool {
+        normalized_name(ign) == normalized_name(player)
+    }
+}
+
+// Forgives a single typo, for names long enough that one letter off is unlikely to be someone else
+pub struct Fuzzy;
+
+impl Matcher for Fuzzy {
+    fn name(&self) -> &'static str {
+        "fuzzy"
+    }
+
+    fn matches(&self, ign: &str, player: &str) -> bool {
+        let (ign, player) = (normalized_name(ign), normalized_name(player));
+        ign.chars().count() >= 4 && distance(&ign, &player) <= 1
+    }
+}
+
+// Matches in game names to players one to one, returning the index of each name's player and the
+// strategy that found it. Each strategy is tried for every name before falling back to the next,
+// so a looser match never takes the player an exact one was waiting for
+pub fn assign(
+    matchers: &[Box<dyn Matcher>],
+    igns: &[String],
+    players: &[&str],
+    allowed: impl Fn(usize, &str) -> bool,
+) -> Vec<Option<(usize, &'static str)>> {
+    let mut assigned = vec![None; igns.len()];
+    let mut claimed = vec![false; players.len()];
+
+    for matcher in matchers {
+        for (index, ign) in igns.iter().enumerate() {
+            if assigned[index].is_some() {
+                continue;
+            }
+
+            let found = players
+                .iter()
+                .enumerate()
+                .find(|&(player, name)| {
+                    !claimed[player] && allowed(index, name) && matcher.matches(ign, name)
+                })
+                .map(|(player, _)| player);
+            if let Some(player) = found {
+                claimed[player] = true;
+                assigned[index] = Some((player, matcher.name()));
+            }
+        }
+    }
+
+    assigned
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    Exact,
+    Normalized,
+    Fuzzy,
+}
+
+impl Strategy {
+    pub fn build(self) -> Box<dyn Matcher> {
+        match self {
+            Strategy::Exact => Box::new(Exact),
+            Strategy::Normalized => Box::new(Normalized),
+            Strategy::Fuzzy => Box::new(Fuzzy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matchers(strategies: &[Strategy]) -> Vec<Box<dyn Matcher>> {
+        strategies
+            .iter()
+            .map(|&strategy| strategy.build())
+            .collect()
+    }
+
+    fn igns(names: &[&str]) -> Vec<String> {
+        names.iter().map(|&name| name.to_owned()).collect()
+    }
+
+    #[test]
+    fn exact_matches_beat_earlier_fuzzy_ones() {
+        let matchers = matchers(&[Strategy::Exact, Strategy::Fuzzy]);
+        let assigned = assign(&matchers, &igns(&["Sam2", "Sam1"]), &["Sam1"], |_, _| true);
+
+        assert_eq!(assigned, vec![None, Some((0, "exact"))]);
+    }
+
+    #[test]
+    fn players_are_only_matched_once() {
+        let matchers = matchers(&[Strategy::Exact]);
+        let assigned = assign(&matchers, &igns(&["Red", "Red"]), &["Red"], |_, _| true);
+
+        assert_eq!(assigned, vec![Some((0, "exact")), None]);
+    }
+
+    #[test]
+    fn later_strategies_match_what_is_left() {
+        let matchers = matchers(&[Strategy::Exact, Strategy::Normalized, Strategy::Fuzzy]);
+        let assigned = assign(
+            &matchers,
+            &igns(&["blue", "Greem", "Red"]),
+            &["Red", "Blue", "Green"],
+            |_, _| true,
+        );
+
+        assert_eq!(
+            assigned,
+            vec![
+                Some((1, "normalized")),
+                Some((2, "fuzzy")),
+                Some((0, "exact"))
+            ]
+        );
+    }
+
+    #[test]
+    fn disallowed_players_are_skipped() {
+        let matchers = matchers(&[Strategy::Exact]);
+        let assigned = assign(&matchers, &igns(&["Red"]), &["Red"], |_, name| {
+            name != "Red"
+        });
+
+        assert_eq!(assigned, vec![None]);
+    }
+
+    #[test]
+    fn fuzzy_needs_a_long_enough_name() {
+        assert!(Fuzzy.matches("Greem", "Green"));
+        assert!(!Fuzzy.matches("Rad", "Red"));
+        assert!(!Fuzzy.matches("Grape", "Green"));
+    }
+}
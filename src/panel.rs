@@ -0,0 +1,53 @@
+use twilight_model::application::component::{button::ButtonStyle, ActionRow, Button, Component};
+
+pub const TITLE: &str = "Game controls";
+
+// Buttons carry their action after this prefix
+const PANEL_ID: &str = "taskinator-panel-";
+
+#[derive(Clone, Copy)]
+pub enum PanelAction {
+    Meeting,
+    Pause,
+    EndGame,
+}
+
+impl PanelAction {
+    fn id(self) -> &'static str {
+        match self {
+            PanelAction::Meeting => "meeting",
+            PanelAction::Pause => "pause",
+            PanelAction::EndGame => "endgame",
+        }
+    }
+}
+
+pub fn action_of(custom_id: &str) -> Option<PanelAction> {
+    match custom_id.strip_prefix(PANEL_ID)? {
+        "meeting" => Some(PanelAction::Meeting),
+        "pause" => Some(PanelAction::Pause),
+        "endgame" => Some(PanelAction::EndGame),
+        _ => None,
+    }
+}
+
+pub fn buttons() -> Vec<Component> {
+    let button = |action: PanelAction, label: &str, style| {
+        Component::Button(Button {
+            custom_id: Some(format!("{}{}", PANEL_ID, action.id())),
+            disabled: false,
+            emoji: None,
+            label: Some(label.to_owned()),
+            style,
+            url: None,
+        })
+    };
+
+    vec![Component::ActionRow(ActionRow {
+        components: vec![
+            button(PanelAction::Meeting, "Meeting", ButtonStyle::Primary),
+            button(PanelAction::Pause, "Pause / Resume", ButtonStyle::Secondary),
+            button(PanelAction::EndGame, "End game", ButtonStyle::Danger),
+        ],
+    })]
+}
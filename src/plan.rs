@@ -0,0 +1,113 @@
+use std::fmt;
+
+use crate::http::MemberUpdate;
+
+// Every member update for one transition, worked out up front so it can be logged or previewed
+// before anything is sent. Waves are applied in order, each one finishing before the next starts.
+pub struct MutePlan {
+    name: &'static str,
+    waves: Vec<Vec<MemberUpdate>>,
+}
+
+impl MutePlan {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            waves: Vec::new(),
+        }
+    }
+
+    pub fn wave(mut self, updates: Vec<MemberUpdate>) -> Self {
+        if !updates.is_empty() {
+            self.waves.push(updates);
+        }
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.waves.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waves.is_empty()
+    }
+
+    pub fn into_waves(self) -> Vec<Vec<MemberUpdate>> {
+        self.waves
+    }
+}
+
+impl fmt::Display for MutePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, wave) in self.waves.iter().enumerate() {
+            writeln!(f, "Wave {}:", index + 1)?;
+            for update in wave {
+                writeln!(f, "- {}", update)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::id::{ChannelId, GuildId, UserId};
+
+    use super::*;
+
+    fn update(user: u64) -> MemberUpdate {
+        MemberUpdate::new(GuildId(1), UserId(user), "test")
+    }
+
+    #[test]
+    fn empty_waves_are_dropped() {
+        let plan = MutePlan::new("test").wave(Vec::new()).wave(Vec::new());
+
+        assert!(plan.is_empty());
+        assert_eq!(plan.len(), 0);
+        assert_eq!(plan.to_string(), "");
+    }
+
+    #[test]
+    fn len_counts_every_wave() {
+        let plan = MutePlan::new("test")
+            .wave(vec![update(2), update(3)])
+            .wave(Vec::new())
+            .wave(vec![update(4)]);
+
+        assert!(!plan.is_empty());
+        assert_eq!(plan.len(), 3);
+        assert_eq!(plan.into_waves().len(), 2);
+    }
+
+    #[test]
+    fn lists_waves_in_order() {
+        let plan = MutePlan::new("test")
+            .wave(vec![update(2).mute(true).deaf(false)])
+            .wave(vec![update(3).channel_id(ChannelId(4))]);
+
+        assert_eq!(
+            plan.to_string(),
+            "Wave 1:\n- <@2> mute undeafen (test)\nWave 2:\n- <@3> move to <#4> (test)\n"
+        );
+    }
+
+    #[test]
+    fn settled_compares_only_what_changes() {
+        let update = update(2).mute(true);
+
+        assert!(update.settled(true, false, None));
+        assert!(update.settled(true, true, Some(ChannelId(4))));
+        assert!(!update.settled(false, false, None));
+
+        let update = update.channel_id(ChannelId(4));
+        assert!(update.settled(true, false, Some(ChannelId(4))));
+        assert!(!update.settled(true, false, Some(ChannelId(5))));
+        assert!(!update.settled(true, false, None));
+    }
+}
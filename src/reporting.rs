@@ -0,0 +1,45 @@
+#[cfg(feature = "sentry")]
+pub type Guard = sentry::ClientInitGuard;
+
+#[cfg(not(feature = "sentry"))]
+pub type Guard = ();
+
+// Failures owners are told about are also sent to Sentry, along with any panic, when sentry_dsn
+// is set. Reports are sent until the guard is dropped, so it's kept for as long as the bot runs
+#[cfg(feature = "sentry")]
+pub fn init(dsn: Option<&str>) -> Option<Guard> {
+    let dsn = dsn?;
+
+    Some(sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            ..sentry::ClientOptions::default()
+        },
+    )))
+}
+
+#[cfg(not(feature = "sentry"))]
+#[allow(clippy::unnecessary_wraps)]
+pub fn init(dsn: Option<&str>) -> Option<Guard> {
+    if dsn.is_some() {
+        tracing::warn!("sentry_dsn is set, but this build does not include error reporting");
+    }
+
+    None
+}
+
+#[cfg(feature = "sentry")]
+pub fn report(message: &str, tags: &[(&str, String)]) {
+    sentry::with_scope(
+        |scope| {
+            for (key, value) in tags {
+                scope.set_tag(key, value);
+            }
+        },
+        || sentry::capture_message(message, sentry::Level::Error),
+    );
+}
+
+#[cfg(not(feature = "sentry"))]
+pub fn report(_message: &str, _tags: &[(&str, String)]) {}
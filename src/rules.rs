@@ -0,0 +1,41 @@
+use serde::Deserialize;
+use twilight_model::id::RoleId;
+
+pub const TRIGGERS: &[&str] = &["game-start", "death", "meeting", "game-end"];
+pub const RULE_KEYS: &[&str] = &["on", "nth", "say", "give_role", "unmute_ghosts"];
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Trigger {
+    GameStart,
+    Death,
+    Meeting,
+    GameEnd,
+}
+
+// A house rule from the config: when something happens in a game, or only the nth time it does
+// that game, the bot does everything the rule lists
+#[derive(Clone, Debug, Deserialize)]
+pub struct HouseRule {
+    pub on: Trigger,
+    pub nth: Option<u32>,
+    // Posted to the broadcast channel, with {player} standing for whoever died
+    pub say: Option<String>,
+    // Given to whoever died until the game ends, e.g. a consolation role for the first death
+    pub give_role: Option<RoleId>,
+    // Lets the dead talk in meetings for the rest of the game
+    #[serde(default)]
+    pub unmute_ghosts: bool,
+}
+
+impl HouseRule {
+    pub fn applies(&self, trigger: Trigger, count: u32) -> bool {
+        self.on == trigger && self.nth.map_or(true, |nth| nth == count)
+    }
+
+    pub fn message(&self, player: Option<&str>) -> Option<String> {
+        self.say
+            .as_ref()
+            .map(|say| say.replace("{player}", player.unwrap_or("Nobody")))
+    }
+}
@@ -0,0 +1,540 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant, SystemTime},
+};
+
+use parking_lot::RwLock;
+use tokio::sync::broadcast;
+use twilight_model::id::{ChannelId, UserId};
+
+// Oldest ~ident names are dropped beyond this, so busy public servers don't grow it forever
+const MAX_PLAYER_NAMES: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    PreGame,
+    InGame,
+    InMeeting,
+    GameOver,
+}
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::PreGame => "pre-game",
+            Phase::InGame => "in-game",
+            Phase::InMeeting => "meeting",
+            Phase::GameOver => "game-over",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SessionEvent {
+    PhaseChanged(Phase),
+    PauseChanged(bool),
+    PlayerNamed {
+        user_id: UserId,
+        name: String,
+    },
+    PlayerForced {
+        user_id: UserId,
+        name: Option<String>,
+    },
+    // Each member is forced onto the other's player in one go, and with keep_names their ~ident
+    // names are exchanged too
+    PlayersSwapped {
+        first: (UserId, String),
+        second: (UserId, String),
+        keep_names: bool,
+    },
+    PlayerNamesUsed(Vec<UserId>),
+    PlayerNamesPruned(Duration),
+    MeetingStarted {
+        dead: HashSet<String>,
+        homes: HashMap<UserId, ChannelId>,
+    },
+    MeetingForced(bool),
+    // Set by a house rule, the dead talk in meetings for the rest of the game
+    GhostsUnmuted(bool),
+    MeetingAttended {
+        present: HashSet<String>,
+        absent: HashSet<String>,
+    },
+    DeadSeen(HashSet<String>),
+    PlayerSeen(UserId),
+    PlayerKicked(UserId),
+    PlayerUnkicked(UserId),
+    PlayerDeparted {
+        user_id: UserId,
+        at: Instant,
+    },
+    UpdatesFailed(u32),
+    SpectatorsSeated(Vec<UserId>),
+    Queued(UserId),
+    Dequeued(Vec<UserId>),
+    LineupPlayed(Vec<UserId>),
+    DuoFormed(UserId, UserId),
+    DuoSplit(UserId),
+    TeamsShuffled(Vec<UserId>),
+    PlayerParked(UserId),
+    PlayerUnparked(UserId),
+    OutageStarted(Instant),
+    OutageEnded,
+}
+
+struct Session {
+    phase: Phase,
+    paused: bool,
+    // ~ident names, with when each was last used to match a player. This is wall clock time, as
+    // names are kept for days and the host's PC may sleep in between, which stops an Instant
+    player_names: HashMap<UserId, (String, SystemTime)>,
+    // Admin bindings that take precedence over both ~ident and automatic matching
+    forced_names: HashMap<UserId, String>,
+    meeting_dead: HashSet<String>,
+    // How many meetings each dead player has sat through since dying
+    dead_meetings: HashMap<String, u32>,
+    // How many meetings in a row each living player has missed from voice
+    missed_meetings: HashMap<String, u32>,
+    // Memory reads can briefly show players as dead, eg. right after a map change, so deaths
+    // nobody reported are only trusted once two polls in a row agree
+    dead_last_poll: HashSet<String>,
+    confirmed_dead: HashSet<String>,
+    // Split living channels members were gathered from for the current meeting
+    homes: HashMap<UserId, ChannelId>,
+    // Meeting called with ~meeting that the game hasn't reported, so it must not be ended for that
+    meeting_forced: bool,
+    ghosts_unmuted: bool,
+    // Time spent in rounds this game, with the clock stopped for meetings, and meetings held. Kept
+    // after the game ends for its results, and reset when the next one starts
+    round_time: Duration,
+    round_started: Option<Instant>,
+    meetings: u32,
+    // Member updates that failed this game, counted against the failure budget
+    failed_updates: u32,
+    // Members matched to a player this game, with the time they dropped from the game if they have
+    departures: HashMap<UserId, Option<Instant>>,
+    // Members excluded from automation by ~session kick
+    kicked: HashSet<UserId>,
+    // When Discord or the home server became unavailable, voice changes are held off until it's back
+    outage: Option<Instant>,
+    // Members moved to the AFK channel after dropping from the game, excluded until they ~back
+    parked: HashSet<UserId>,
+    // Members left watching this game because the lobby was full
+    spectators: HashSet<UserId>,
+    // Members waiting for a place in a game, in the order they'll be rotated in
+    queue: Vec<UserId>,
+    // How many games in a row each member of the last lineup has played
+    streaks: HashMap<UserId, u32>,
+    // Pairs ~teams shuffle keeps together, both ways round
+    duos: HashMap<UserId, UserId>,
+    // Who sat out the last ~teams shuffle, so they get a place in the next
+    sat_out: HashSet<UserId>,
+}
+
+impl Session {
+    // Returns whether the event changed the session
+    fn apply(&mut self, event: &SessionEvent) -> bool {
+        match event {
+            SessionEvent::PhaseChanged(phase) => {
+                if matches!(phase, Phase::PreGame | Phase::GameOver) {
+                    self.departures.clear();
+                    self.homes.clear();
+                    self.meeting_forced = false;
+                    self.ghosts_unmuted = false;
+                    self.failed_updates = 0;
+                    self.spectators.clear();
+                    self.meeting_dead.clear();
+                    self.dead_meetings.clear();
+                    self.missed_meetings.clear();
+                    self.dead_last_poll.clear();
+                    self.confirmed_dead.clear();
+                }
+
+                if *phase == Phase::InGame && matches!(self.phase, Phase::PreGame | Phase::GameOver)
+                {
+                    self.round_time = Duration::default();
+                    self.meetings = 0;
+                }
+                if *phase == Phase::InGame {
+                    self.round_started.get_or_insert_with(Instant::now);
+                } else if let Some(started) = self.round_started.take() {
+                    self.round_time += started.elapsed();
+                }
+
+                replace(&mut self.phase, *phase)
+            }
+            SessionEvent::PauseChanged(paused) => replace(&mut self.paused, *paused),
+            SessionEvent::PlayerNamed { user_id, name } => {
+                let previous = self
+                    .player_names
+                    .insert(*user_id, (name.clone(), SystemTime::now()));
+
+                if self.player_names.len() > MAX_PLAYER_NAMES {
+                    let oldest = self
+                        .player_names
+                        .iter()
+                        .min_by_key(|(_, (_, last_used))| *last_used)
+                        .map(|(&user_id, _)| user_id);
+                    if let Some(oldest) = oldest {
+                        self.player_names.remove(&oldest);
+                    }
+                }
+
+                previous.map(|(previous, _)| previous).as_ref() != Some(name)
+            }
+            SessionEvent::PlayerNamesUsed(user_ids) => {
+                let now = SystemTime::now();
+                let mut changed = false;
+                for user_id in user_ids {
+                    if let Some((_, last_used)) = self.player_names.get_mut(user_id) {
+                        *last_used = now;
+                        changed = true;
+                    }
+                }
+                changed
+            }
+            SessionEvent::PlayerNamesPruned(max_age) => {
+                let before = self.player_names.len();
+                // A name last used in the future means the clock was turned back, so it's kept
+                self.player_names.retain(|_, (_, last_used)| {
+                    last_used.elapsed().map_or(true, |age| age <= *max_age)
+                });
+                self.player_names.len() != before
+            }
+            SessionEvent::PlayerForced { user_id, name } => match name {
+                Some(name) => {
+                    self.forced_names.insert(*user_id, name.clone()).as_ref() != Some(name)
+                }
+                None => self.forced_names.remove(user_id).is_some(),
+            },
+            SessionEvent::PlayersSwapped {
+                first,
+                second,
+                keep_names,
+            } => {
+                self.forced_names.insert(first.0, second.1.clone());
+                self.forced_names.insert(second.0, first.1.clone());
+                if *keep_names {
+                    let first_name = self.player_names.remove(&first.0);
+                    if let Some(name) = self.player_names.remove(&second.0) {
+                        self.player_names.insert(first.0, name);
+                    }
+                    if let Some(name) = first_name {
+                        self.player_names.insert(second.0, name);
+                    }
+                }
+                true
+            }
+            SessionEvent::MeetingStarted { dead, homes } => {
+                self.meetings += 1;
+                for name in dead {
+                    *self.dead_meetings.entry(name.clone()).or_default() += 1;
+                }
+                self.meeting_dead = dead.clone();
+                self.homes = homes.clone();
+                true
+            }
+            SessionEvent::MeetingForced(forced) => replace(&mut self.meeting_forced, *forced),
+            SessionEvent::GhostsUnmuted(unmuted) => replace(&mut self.ghosts_unmuted, *unmuted),
+            SessionEvent::MeetingAttended { present, absent } => {
+                for name in present {
+                    self.missed_meetings.remove(name);
+                }
+                for name in absent {
+                    *self.missed_meetings.entry(name.clone()).or_default() += 1;
+                }
+                true
+            }
+            SessionEvent::DeadSeen(dead) => {
+                let confirmed = dead.intersection(&self.dead_last_poll).cloned().collect();
+                self.dead_last_poll = dead.clone();
+                replace(&mut self.confirmed_dead, confirmed)
+            }
+            SessionEvent::PlayerSeen(user_id) => {
+                self.departures.insert(*user_id, None) != Some(None)
+            }
+            SessionEvent::PlayerKicked(user_id) => self.kicked.insert(*user_id),
+            SessionEvent::PlayerUnkicked(user_id) => self.kicked.remove(user_id),
+            SessionEvent::OutageStarted(at) => {
+                if self.outage.is_some() {
+                    false
+                } else {
+                    self.outage = Some(*at);
+                    true
+                }
+            }
+            SessionEvent::OutageEnded => self.outage.take().is_some(),
+            SessionEvent::PlayerParked(user_id) => self.parked.insert(*user_id),
+            SessionEvent::PlayerUnparked(user_id) => self.parked.remove(user_id),
+            SessionEvent::UpdatesFailed(failed) => {
+                self.failed_updates = self.failed_updates.saturating_add(*failed);
+                *failed > 0
+            }
+            SessionEvent::SpectatorsSeated(user_ids) => {
+                self.spectators = user_ids.iter().copied().collect();
+                for user_id in user_ids {
+                    if !self.queue.contains(user_id) {
+                        self.queue.push(*user_id);
+                    }
+                }
+                !user_ids.is_empty()
+            }
+            SessionEvent::Queued(user_id) => {
+                if self.queue.contains(user_id) {
+                    false
+                } else {
+                    self.queue.push(*user_id);
+                    true
+                }
+            }
+            SessionEvent::Dequeued(user_ids) => {
+                let queued = self.queue.len();
+                self.queue.retain(|user_id| !user_ids.contains(user_id));
+                self.queue.len() != queued
+            }
+            SessionEvent::LineupPlayed(user_ids) => {
+                self.queue.retain(|user_id| !user_ids.contains(user_id));
+                self.streaks = user_ids
+                    .iter()
+                    .map(|user_id| (*user_id, self.streaks.get(user_id).map_or(1, |s| s + 1)))
+                    .collect();
+                true
+            }
+            SessionEvent::DuoFormed(user_id, partner) => {
+                for member in &[*user_id, *partner] {
+                    if let Some(previous) = self.duos.remove(member) {
+                        self.duos.remove(&previous);
+                    }
+                }
+                self.duos.insert(*user_id, *partner);
+                self.duos.insert(*partner, *user_id);
+                true
+            }
+            SessionEvent::DuoSplit(user_id) => match self.duos.remove(user_id) {
+                Some(partner) => {
+                    self.duos.remove(&partner);
+                    true
+                }
+                None => false,
+            },
+            SessionEvent::TeamsShuffled(sitting_out) => {
+                replace(&mut self.sat_out, sitting_out.iter().copied().collect())
+            }
+            SessionEvent::PlayerDeparted { user_id, at } => {
+                match self.departures.get_mut(user_id) {
+                    Some(departed @ None) => {
+                        *departed = Some(*at);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+fn replace<T: PartialEq>(current: &mut T, new: T) -> bool {
+    let changed = *current != new;
+    *current = new;
+    changed
+}
+
+// All session state changes go through `apply`, and every change is published to subscribers
+pub struct SessionStore {
+    session: RwLock<Session>,
+    events: broadcast::Sender<SessionEvent>,
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(64);
+
+        Self {
+            session: RwLock::new(Session {
+                phase: Phase::PreGame,
+                paused: false,
+                player_names: HashMap::new(),
+                forced_names: HashMap::new(),
+                meeting_dead: HashSet::new(),
+                dead_meetings: HashMap::new(),
+                missed_meetings: HashMap::new(),
+                dead_last_poll: HashSet::new(),
+                confirmed_dead: HashSet::new(),
+                homes: HashMap::new(),
+                meeting_forced: false,
+                ghosts_unmuted: false,
+                round_time: Duration::default(),
+                round_started: None,
+                meetings: 0,
+                failed_updates: 0,
+                departures: HashMap::new(),
+                kicked: HashSet::new(),
+                outage: None,
+                parked: HashSet::new(),
+                spectators: HashSet::new(),
+                queue: Vec::new(),
+                streaks: HashMap::new(),
+                duos: HashMap::new(),
+                sat_out: HashSet::new(),
+            }),
+            events,
+        }
+    }
+
+    pub fn apply(&self, event: SessionEvent) {
+        if self.session.write().apply(&event) {
+            tracing::debug!("Session event: {:?}", event);
+
+            // Nobody listening is not an error
+            self.events.send(event).ok();
+        }
+    }
+
+    // Flipped under one lock, so two toggles at once can't both land on the same state. Returns
+    // whether automation is now paused
+    pub fn toggle_paused(&self) -> bool {
+        let paused = {
+            let mut session = self.session.write();
+            let paused = !session.paused;
+            session.apply(&SessionEvent::PauseChanged(paused));
+            paused
+        };
+
+        let event = SessionEvent::PauseChanged(paused);
+        tracing::debug!("Session event: {:?}", event);
+        self.events.send(event).ok();
+
+        paused
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.session.read().phase
+    }
+
+    pub fn paused(&self) -> bool {
+        self.session.read().paused
+    }
+
+    pub fn player_name(&self, user_id: UserId) -> Option<String> {
+        self.session
+            .read()
+            .player_names
+            .get(&user_id)
+            .map(|(name, _)| name.clone())
+    }
+
+    pub fn player_name_count(&self) -> usize {
+        self.session.read().player_names.len()
+    }
+
+    pub fn forced_name(&self, user_id: UserId) -> Option<String> {
+        self.session.read().forced_names.get(&user_id).cloned()
+    }
+
+    pub fn forced_names(&self) -> HashMap<UserId, String> {
+        self.session.read().forced_names.clone()
+    }
+
+    pub fn meeting_dead(&self) -> HashSet<String> {
+        self.session.read().meeting_dead.clone()
+    }
+
+    // Deaths reported in a meeting or seen in consecutive polls
+    pub fn death_confirmed(&self, name: &str) -> bool {
+        let session = self.session.read();
+        session.meeting_dead.contains(name) || session.confirmed_dead.contains(name)
+    }
+
+    pub fn dead_meetings(&self, name: &str) -> u32 {
+        self.session
+            .read()
+            .dead_meetings
+            .get(name)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn missed_meetings(&self, name: &str) -> u32 {
+        self.session
+            .read()
+            .missed_meetings
+            .get(name)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn home_channel(&self, user_id: UserId) -> Option<ChannelId> {
+        self.session.read().homes.get(&user_id).copied()
+    }
+
+    pub fn meeting_forced(&self) -> bool {
+        self.session.read().meeting_forced
+    }
+
+    pub fn ghosts_unmuted(&self) -> bool {
+        self.session.read().ghosts_unmuted
+    }
+
+    // The current or last game's round time and meetings
+    pub fn game_clock(&self) -> (Duration, u32) {
+        let session = self.session.read();
+        let running = session
+            .round_started
+            .map_or_else(Duration::default, |started| started.elapsed());
+        (session.round_time + running, session.meetings)
+    }
+
+    pub fn failed_updates(&self) -> u32 {
+        self.session.read().failed_updates
+    }
+
+    pub fn is_seen(&self, user_id: UserId) -> bool {
+        matches!(self.session.read().departures.get(&user_id), Some(None))
+    }
+
+    pub fn is_kicked(&self, user_id: UserId) -> bool {
+        self.session.read().kicked.contains(&user_id)
+    }
+
+    pub fn outage_since(&self) -> Option<Instant> {
+        self.session.read().outage
+    }
+
+    pub fn is_parked(&self, user_id: UserId) -> bool {
+        self.session.read().parked.contains(&user_id)
+    }
+
+    pub fn is_spectating(&self, user_id: UserId) -> bool {
+        self.session.read().spectators.contains(&user_id)
+    }
+
+    pub fn queue(&self) -> Vec<UserId> {
+        self.session.read().queue.clone()
+    }
+
+    pub fn streaks(&self) -> HashMap<UserId, u32> {
+        self.session.read().streaks.clone()
+    }
+
+    pub fn duos(&self) -> HashMap<UserId, UserId> {
+        self.session.read().duos.clone()
+    }
+
+    pub fn sat_out(&self) -> HashSet<UserId> {
+        self.session.read().sat_out.clone()
+    }
+
+    pub fn departed_at(&self, user_id: UserId) -> Option<Instant> {
+        self.session
+            .read()
+            .departures
+            .get(&user_id)
+            .copied()
+            .flatten()
+    }
+}
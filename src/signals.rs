@@ -0,0 +1,45 @@
+use tokio::signal::ctrl_c;
+
+use crate::{bot::Bot, config::Config, Result, CONFIG_PATH};
+
+// Resolves on ctrl+c, or on unix the SIGTERM systemd and Docker use to stop the bot
+#[cfg(unix)]
+pub async fn shutdown() -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+
+    tokio::select! {
+        result = ctrl_c() => result?,
+        _ = terminate.recv() => tracing::info!("Received SIGTERM"),
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn shutdown() -> Result<()> {
+    Ok(ctrl_c().await?)
+}
+
+pub async fn reload(bot: &Bot) -> Result<()> {
+    let config = Config::from_file(CONFIG_PATH).await?;
+    bot.reload_config(&config).await
+}
+
+// Like most daemons, SIGHUP reloads the config
+#[cfg(unix)]
+pub async fn reload_on_hangup(bot: Bot) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    while hangup.recv().await.is_some() {
+        tracing::info!("Received SIGHUP, reloading the config");
+        if let Err(why) = reload(&bot).await {
+            tracing::warn!("Failed to reload the config: {}", why);
+        }
+    }
+
+    Ok(())
+}
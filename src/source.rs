@@ -0,0 +1,279 @@
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use sysinfo::{Pid, ProcessExt, RefreshKind, System, SystemExt};
+use taskinator_communicator::game::{Game, State};
+use tokio::{
+    sync::{mpsc, watch},
+    time::sleep,
+};
+use tracing::Instrument;
+
+use crate::Result;
+
+pub type StateSender = mpsc::UnboundedSender<Option<State>>;
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub state: State,
+    pub read_at: Instant,
+}
+
+pub trait GameSource: Send + Sync + 'static {
+    fn name(&self) -> &'static str;
+
+    // Runs until the source can no longer provide updates, sending `None` if the game it was
+    // watching has been lost
+    fn run(&self, tx: StateSender) -> BoxFuture<'_, Result<()>>;
+}
+
+// Only the game's memory can be read for now: the communicator's states can't be serialized or
+// built from anything else, so there's no remote source to fail over to
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SourceConfig {
+    Memory {
+        // Pin the source to one game when the host runs more than one client
+        pid: Option<Pid>,
+        exe: Option<PathBuf>,
+    },
+}
+
+impl SourceConfig {
+    pub fn build(self) -> Box<dyn GameSource> {
+        match self {
+            SourceConfig::Memory { pid, exe } => Box::new(MemorySource { pid, exe }),
+        }
+    }
+}
+
+pub struct MemorySource {
+    pid: Option<Pid>,
+    exe: Option<PathBuf>,
+}
+
+impl MemorySource {
+    fn find_process(&self, system: &System) -> Option<Pid> {
+        let mut candidates = system
+            .get_process_by_name("Among Us.exe")
+            .into_iter()
+            .filter(|process| self.pid.map_or(true, |pid| process.pid() == pid))
+            .filter(|process| {
+                self.exe
+                    .as_ref()
+                    .map_or(true, |exe| process.exe().starts_with(exe))
+            })
+            .map(ProcessExt::pid)
+            .collect::<Vec<_>>();
+        // Sorted so an unpinned source picks the same game each time it restarts
+        candidates.sort_unstable();
+
+        if candidates.len() > 1 {
+            tracing::warn!(
+                "Found {} Among Us processes {:?}, using {}; \
+                set pid or exe on the source to choose",
+                candidates.len(),
+                candidates,
+                candidates[0]
+            );
+        }
+
+        candidates.first().copied()
+    }
+}
+
+impl GameSource for MemorySource {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn run(&self, tx: StateSender) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            const CONN_RETRY_DELAY: u64 = 5;
+            const POLLING_DELAY: u64 = 2;
+            const MAX_CONSEC_FAILS: u64 = 3;
+
+            let among_us_pid = {
+                let mut system = System::new_with_specifics(RefreshKind::new().with_processes());
+
+                loop {
+                    system.refresh_processes();
+
+                    if let Some(pid) = self.find_process(&system) {
+                        break pid;
+                    }
+
+                    tracing::warn!("Could not find Among Us process... That's a bit sus.");
+                    tracing::warn!("Will retry in {} seconds", CONN_RETRY_DELAY);
+
+                    sleep(Duration::from_secs(CONN_RETRY_DELAY)).await;
+                }
+            };
+
+            tracing::info!("Among Us process found! PID: {}", among_us_pid);
+
+            let among_us = match Game::from_pid(among_us_pid) {
+                Ok(game) => game,
+                Err(why) => {
+                    tracing::error!(
+                        "Opening a connection to the game failed, \
+                                    make sure you have sufficient permissions"
+                    );
+                    return Err(why);
+                }
+            };
+
+            tracing::info!("Established connection to Among Us");
+
+            let mut failure_count = 0;
+            loop {
+                match among_us.state() {
+                    Ok(state) => {
+                        if let State::InGame { players, .. } = &state {
+                            let dead_players = players
+                                .iter()
+                                .filter(|p| p.dead)
+                                .map(|p| &p.name)
+                                .collect::<Vec<_>>();
+                            let impostors = players
+                                .iter()
+                                .filter(|p| p.impostor && !p.dead)
+                                .map(|p| &p.name)
+                                .collect::<Vec<_>>();
+                            tracing::debug!("Impostors:\t{:?}", impostors);
+                            if !dead_players.is_empty() {
+                                tracing::debug!("Dead:\t\t{:?}", dead_players);
+                            }
+                        }
+                        tracing::trace!("{:?}", state);
+                        failure_count = 0;
+                        tx.send(Some(state))?;
+                    }
+                    Err(why) => {
+                        if failure_count < MAX_CONSEC_FAILS {
+                            // If failure count has not reached max, increment but DO NOT update
+                            // the channel
+                            failure_count += 1;
+                            tracing::warn!(
+                                "An error occurred reading Among Us' state ({}/{}). \
+                                This can happen when the game is starting or changing level.",
+                                failure_count,
+                                MAX_CONSEC_FAILS,
+                            );
+                            tracing::warn!("{}", why);
+                        } else {
+                            // At max failure count, log an error and set the channel to None to
+                            // signal no running game
+                            tracing::error!(
+                                "Failed to read Among Us' state again. \
+                                Retries exhausted, has the game closed?"
+                            );
+                            tracing::error!("{}", why);
+                            tx.send(None)?;
+                            return Err(why);
+                        }
+                    }
+                }
+                sleep(Duration::from_secs(POLLING_DELAY)).await;
+            }
+        })
+    }
+}
+
+pub struct Multiplexer {
+    sources: Vec<Box<dyn GameSource>>,
+}
+
+impl Multiplexer {
+    pub fn new(sources: Vec<Box<dyn GameSource>>) -> Self {
+        Self { sources }
+    }
+
+    // Sources are listed in order of preference; updates are forwarded from the most preferred
+    // source which currently has a game, so a source dying fails over to the next without the bot
+    // seeing the game end
+    pub async fn run(self, tx: Arc<watch::Sender<Option<Snapshot>>>) -> Result<()> {
+        const RESTART_DELAY: u64 = 5;
+
+        let names = self.sources.iter().map(|s| s.name()).collect::<Vec<_>>();
+        let (update_tx, mut update_rx) = mpsc::unbounded_channel();
+
+        for (index, source) in self.sources.into_iter().enumerate() {
+            let update_tx = update_tx.clone();
+
+            let span = tracing::info_span!("game_source", source = source.name());
+            tokio::spawn(
+                async move {
+                    loop {
+                        let (source_tx, mut source_rx) = mpsc::unbounded_channel();
+
+                        let forwarder = {
+                            let update_tx = update_tx.clone();
+                            tokio::spawn(async move {
+                                while let Some(state) = source_rx.recv().await {
+                                    let snapshot = state.map(|state| Snapshot {
+                                        state,
+                                        read_at: Instant::now(),
+                                    });
+                                    if update_tx.send((index, snapshot)).is_err() {
+                                        break;
+                                    }
+                                }
+                            })
+                        };
+
+                        if let Err(why) = source.run(source_tx).await {
+                            tracing::warn!("Game source {} stopped: {}", source.name(), why);
+                        }
+
+                        let _forwarded = forwarder.await;
+
+                        if update_tx.send((index, None)).is_err() {
+                            break;
+                        }
+
+                        tracing::info!(
+                            "Restarting game source {} in {} seconds",
+                            source.name(),
+                            RESTART_DELAY
+                        );
+                        sleep(Duration::from_secs(RESTART_DELAY)).await;
+                    }
+                }
+                .instrument(span),
+            );
+        }
+
+        drop(update_tx);
+
+        let mut latest: Vec<Option<Snapshot>> = vec![None; names.len()];
+        let mut active = None;
+
+        while let Some((index, state)) = update_rx.recv().await {
+            latest[index] = state;
+
+            let preferred = latest.iter().position(Option::is_some);
+            if preferred != active {
+                match preferred {
+                    Some(preferred) => {
+                        tracing::info!("Switched to game source {}", names[preferred]);
+                    }
+                    None => tracing::warn!("No game source currently has a game"),
+                }
+                active = preferred;
+            } else if active != Some(index) {
+                // Update from a standby source, nothing to forward
+                continue;
+            }
+
+            tx.send(active.and_then(|active| latest[active].clone()))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use twilight_model::id::{ChannelId, GuildId, MessageId, RoleId, UserId};
+
+use crate::{history::GameHistory, summary::GameResult, Result};
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct GuildSettings {
+    #[serde(default)]
+    pub disabled_commands: BTreeSet<String>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default)]
+    pub impostor_buddies: bool,
+    // Match names after normalizing how they're written, see utils::normalized_name
+    #[serde(default)]
+    pub normalize_names: bool,
+    // Images posted with the result when a game is won
+    #[serde(default)]
+    pub crew_victory_image: Option<String>,
+    #[serde(default)]
+    pub impostor_victory_image: Option<String>,
+    // The living channel ~arm was last used on, when require_arming is set
+    #[serde(default)]
+    pub armed_channel: Option<ChannelId>,
+    // The pinned message kept up to date when status_board is set
+    #[serde(default)]
+    pub status_board: Option<MessageId>,
+    // Roles given by house rules and not yet taken back, kept so a restart can still take them
+    #[serde(default)]
+    pub rule_roles: Vec<(UserId, RoleId)>,
+    // Emoji chosen with ~emoji, by the name of what they show. Kept last, as TOML can't have plain
+    // values after a table
+    #[serde(default)]
+    pub emoji: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct UserSettings {
+    #[serde(default)]
+    pub opt_out: bool,
+    #[serde(default)]
+    pub impostor_buddy: bool,
+    // Discord refused a DM to them, so automatic DMs are skipped until they DM the bot
+    #[serde(default)]
+    pub dms_closed: bool,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct StoreData {
+    // TOML keys must be strings, so guilds are keyed by their ID's string form
+    #[serde(default)]
+    guilds: BTreeMap<String, GuildSettings>,
+    #[serde(default)]
+    users: BTreeMap<String, UserSettings>,
+    // For ~game history, so it survives a restart
+    #[serde(default)]
+    history: GameHistory,
+    // Slow mode quiet channels had before the bot changed it, so a restart can still restore it
+    #[serde(default)]
+    slow_modes: BTreeMap<String, u64>,
+}
+
+pub struct Store {
+    path: PathBuf,
+    data: RwLock<StoreData>,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+
+        let data = if path.exists() {
+            toml::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            StoreData::default()
+        };
+
+        Ok(Self {
+            path,
+            data: RwLock::new(data),
+        })
+    }
+
+    pub fn contains(&self, guild_id: GuildId) -> bool {
+        self.data.read().guilds.contains_key(&guild_id.to_string())
+    }
+
+    pub fn guild(&self, guild_id: GuildId) -> GuildSettings {
+        self.data
+            .read()
+            .guilds
+            .get(&guild_id.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn update_guild<T>(
+        &self,
+        guild_id: GuildId,
+        f: impl FnOnce(&mut GuildSettings) -> T,
+    ) -> Result<T> {
+        let mut data = self.data.write();
+        let result = f(data.guilds.entry(guild_id.to_string()).or_default());
+
+        self.save(&data)?;
+
+        Ok(result)
+    }
+
+    pub fn user(&self, user_id: UserId) -> UserSettings {
+        self.data
+            .read()
+            .users
+            .get(&user_id.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn update_user<T>(
+        &self,
+        user_id: UserId,
+        f: impl FnOnce(&mut UserSettings) -> T,
+    ) -> Result<T> {
+        let mut data = self.data.write();
+        let result = f(data.users.entry(user_id.to_string()).or_default());
+
+        self.save(&data)?;
+
+        Ok(result)
+    }
+
+    pub fn history(&self) -> GameHistory {
+        self.data.read().history.clone()
+    }
+
+    pub fn record_game(&self, game: GameResult) -> Result<()> {
+        let mut data = self.data.write();
+        data.history.record(game);
+
+        self.save(&data)
+    }
+
+    pub fn slow_modes(&self) -> Vec<(ChannelId, u64)> {
+        self.data
+            .read()
+            .slow_modes
+            .iter()
+            .filter_map(|(channel_id, &rate_limit)| {
+                Some((ChannelId(channel_id.parse().ok()?), rate_limit))
+            })
+            .collect()
+    }
+
+    pub fn set_slow_mode(&self, channel_id: ChannelId, rate_limit: Option<u64>) -> Result<()> {
+        let mut data = self.data.write();
+        match rate_limit {
+            Some(rate_limit) => data.slow_modes.insert(channel_id.to_string(), rate_limit),
+            None => data.slow_modes.remove(&channel_id.to_string()),
+        };
+
+        self.save(&data)
+    }
+
+    fn save(&self, data: &StoreData) -> Result<()> {
+        fs::write(&self.path, toml::to_string(data)?)?;
+
+        Ok(())
+    }
+}
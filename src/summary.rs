@@ -0,0 +1,120 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    time::{Duration, Instant, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use taskinator_communicator::game::Player;
+
+const MVP_COUNT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Team {
+    Crew,
+    Impostors,
+}
+
+impl Team {
+    // Only wins by votes or kills can be told from who is still alive
+    pub fn from_survivors(players: &[Player]) -> Option<Self> {
+        let (impostors, crew) = players
+            .iter()
+            .filter(|p| !p.dead)
+            .partition::<Vec<_>, _>(|p| p.impostor);
+
+        if impostors.is_empty() {
+            Some(Team::Crew)
+        } else if impostors.len() >= crew.len() {
+            Some(Team::Impostors)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PlayerResult {
+    pub name: String,
+    pub impostor: bool,
+    pub survived: bool,
+}
+
+// Plain values come first, as TOML can't have them after the tables SystemTime and Duration
+// are written as
+#[derive(Clone, Deserialize, Serialize)]
+pub struct GameResult {
+    pub meetings: u32,
+    // The communicator doesn't report how a game ended, so wins by tasks or sabotage are unknown
+    pub winner: Option<Team>,
+    pub ended: SystemTime,
+    pub duration: Duration,
+    pub players: Vec<PlayerResult>,
+}
+
+// Games played from the first game after the last summary until ~wrapup or the session goes idle
+pub struct SessionSummary {
+    last_game: Instant,
+    games: Vec<GameResult>,
+}
+
+impl SessionSummary {
+    pub fn new() -> Self {
+        Self {
+            last_game: Instant::now(),
+            games: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, game: GameResult) {
+        self.last_game = Instant::now();
+        self.games.push(game);
+    }
+
+    pub fn is_idle(&self, timeout: Duration) -> bool {
+        self.last_game.elapsed() > timeout
+    }
+
+    pub fn digest(&self) -> String {
+        let play_time = self.games.iter().map(|g| g.duration).sum::<Duration>();
+        let wins = |team| self.games.iter().filter(|g| g.winner == Some(team)).count();
+
+        let mut survivals = HashMap::<&str, u32>::new();
+        for player in self.games.iter().flat_map(|g| &g.players) {
+            let survived = survivals.entry(player.name.as_str()).or_default();
+            if player.survived {
+                *survived += 1;
+            }
+        }
+        let mut mvps = survivals.into_iter().collect::<Vec<_>>();
+        mvps.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let mut digest = String::new();
+        let _ = writeln!(digest, "Games played: {}", self.games.len());
+        let _ = writeln!(
+            digest,
+            "Play time: {}h {}m",
+            play_time.as_secs() / 3600,
+            play_time.as_secs() / 60 % 60
+        );
+        let _ = writeln!(
+            digest,
+            "Crew wins: {}, impostor wins: {}, undecided: {}",
+            wins(Team::Crew),
+            wins(Team::Impostors),
+            self.games.len() - wins(Team::Crew) - wins(Team::Impostors)
+        );
+        let _ = write!(
+            digest,
+            "Most games survived: {}",
+            mvps.iter()
+                .take(MVP_COUNT)
+                .map(|(name, survived)| format!("{} ({})", name, survived))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        digest
+    }
+}
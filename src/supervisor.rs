@@ -0,0 +1,173 @@
+use std::{collections::BTreeMap, fmt, future::Future, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use tokio::{sync::mpsc, time::sleep};
+
+use crate::Result;
+
+// Doubled after each failure in a row, so a task that can't work, e.g. for want of a permission,
+// isn't retried every few seconds for as long as the bot runs
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(5 * 60);
+// A restarted task that stays up this long has recovered
+const RECOVERED_AFTER: Duration = Duration::from_secs(60);
+const ESSENTIAL_RESTARTS: u32 = 3;
+
+#[derive(Clone, Copy)]
+pub enum Policy {
+    // Restarted for as long as the bot runs
+    Restart,
+    // Restarted a few times, after which the bot can't work and should shut down
+    Essential,
+}
+
+#[derive(Clone, Copy)]
+pub enum Status {
+    Running,
+    Restarting,
+    Failed,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Running => "running",
+            Status::Restarting => "restarting",
+            Status::Failed => "failed",
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct TaskInfo {
+    pub status: Status,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+// Only the first failure in a row is reported, and then the recovery, so owners aren't told about
+// every restart of a task that keeps failing
+pub enum Report {
+    Died { task: &'static str, reason: String },
+    Recovered { task: &'static str },
+    Fatal { task: &'static str },
+}
+
+// Background tasks are spawned through the supervisor, so a task dying is reported rather than
+// silently dropping its JoinHandle
+#[derive(Clone)]
+pub struct Supervisor {
+    reports: mpsc::UnboundedSender<Report>,
+    tasks: Arc<Mutex<BTreeMap<&'static str, TaskInfo>>>,
+}
+
+impl Supervisor {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<Report>) {
+        let (reports, rx) = mpsc::unbounded_channel();
+        (
+            Self {
+                reports,
+                tasks: Arc::new(Mutex::new(BTreeMap::new())),
+            },
+            rx,
+        )
+    }
+
+    pub fn tasks(&self) -> Vec<(&'static str, TaskInfo)> {
+        self.tasks
+            .lock()
+            .iter()
+            .map(|(&task, info)| (task, info.clone()))
+            .collect()
+    }
+
+    fn update(&self, task: &'static str, update: impl FnOnce(&mut TaskInfo)) {
+        update(self.tasks.lock().entry(task).or_insert(TaskInfo {
+            status: Status::Running,
+            restarts: 0,
+            last_error: None,
+        }));
+    }
+
+    pub fn spawn<F, Fut>(&self, task: &'static str, policy: Policy, start: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let supervisor = self.clone();
+
+        tokio::spawn(async move {
+            let reports = &supervisor.reports;
+            let mut restarts = 0;
+            // Failures since the task last stayed up long enough to count as recovered
+            let mut failures = 0;
+
+            loop {
+                supervisor.update(task, |info| info.status = Status::Running);
+
+                // Running each attempt as its own task means panics are caught as well
+                let mut attempt = tokio::spawn(start());
+                let finished = if failures > 0 {
+                    tokio::select! {
+                        outcome = &mut attempt => Some(outcome),
+                        () = sleep(RECOVERED_AFTER) => None,
+                    }
+                } else {
+                    None
+                };
+                if finished.is_none() && failures > 0 {
+                    tracing::info!("Task {} has recovered", task);
+                    failures = 0;
+                    reports.send(Report::Recovered { task }).ok();
+                }
+                let outcome = if let Some(outcome) = finished {
+                    outcome
+                } else {
+                    attempt.await
+                };
+
+                let reason = match outcome {
+                    Ok(Ok(())) => "it stopped unexpectedly".to_owned(),
+                    Ok(Err(why)) => why.to_string(),
+                    Err(why) => why.to_string(),
+                };
+
+                tracing::error!("Task {} died: {}", task, reason);
+                let fatal = matches!(policy, Policy::Essential) && failures == ESSENTIAL_RESTARTS;
+                supervisor.update(task, |info| {
+                    info.status = if fatal {
+                        Status::Failed
+                    } else {
+                        Status::Restarting
+                    };
+                    info.last_error = Some(reason.clone());
+                });
+
+                if failures == 0 {
+                    reports.send(Report::Died { task, reason }).ok();
+                }
+                // Nobody listening means the bot is shutting down
+                if reports.is_closed() {
+                    break;
+                }
+
+                if fatal {
+                    reports.send(Report::Fatal { task }).ok();
+                    break;
+                }
+
+                restarts += 1;
+                failures += 1;
+                supervisor.update(task, |info| info.restarts = restarts);
+                sleep(restart_delay(failures)).await;
+                tracing::info!("Restarting task {}", task);
+            }
+        });
+    }
+}
+
+fn restart_delay(failures: u32) -> Duration {
+    let factor = 2_u32.saturating_pow(failures.saturating_sub(1));
+
+    RESTART_DELAY.saturating_mul(factor).min(MAX_RESTART_DELAY)
+}
@@ -0,0 +1,49 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::seq::SliceRandom;
+use twilight_model::id::UserId;
+
+pub struct Lineup {
+    pub playing: Vec<UserId>,
+    pub sitting_out: Vec<UserId>,
+}
+
+// Randomly fills the lobby from the candidates. Duos who are both candidates play or sit out
+// together, and anyone who sat out the last shuffle is seated before anyone who didn't
+pub fn shuffle(
+    candidates: &[UserId],
+    duos: &HashMap<UserId, UserId>,
+    sat_out: &HashSet<UserId>,
+    slots: usize,
+) -> Lineup {
+    let mut units: Vec<Vec<UserId>> = Vec::new();
+    for &user_id in candidates {
+        if units.iter().any(|unit| unit.contains(&user_id)) {
+            continue;
+        }
+        match duos.get(&user_id) {
+            Some(&partner) if partner != user_id && candidates.contains(&partner) => {
+                units.push(vec![user_id, partner]);
+            }
+            _ => units.push(vec![user_id]),
+        }
+    }
+
+    units.shuffle(&mut rand::thread_rng());
+    // Stable, so the order within each group stays random
+    units.sort_by_key(|unit| !unit.iter().any(|user_id| sat_out.contains(user_id)));
+
+    let mut lineup = Lineup {
+        playing: Vec::new(),
+        sitting_out: Vec::new(),
+    };
+    for unit in units {
+        if lineup.playing.len() + unit.len() <= slots {
+            lineup.playing.extend(unit);
+        } else {
+            lineup.sitting_out.extend(unit);
+        }
+    }
+
+    lineup
+}
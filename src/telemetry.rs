@@ -0,0 +1,138 @@
+#[cfg(feature = "telemetry")]
+use std::sync::Arc;
+use std::{collections::VecDeque, time::Duration};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+#[cfg(feature = "telemetry")]
+use crate::Result;
+
+#[cfg(feature = "telemetry")]
+const REPORT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Transitions kept for the latency percentiles, older ones are dropped
+const LATENCY_WINDOW: usize = 256;
+
+// Aggregate counts only: nothing here identifies a guild, user or player
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct Snapshot {
+    pub games: u64,
+    pub members_seen: u64,
+    pub members_matched: u64,
+    pub mute_waves: u64,
+    pub mute_wave_millis: u64,
+    pub source_failures: u64,
+    pub transitions: u64,
+    pub latency_p50_millis: Option<u64>,
+    pub latency_p95_millis: Option<u64>,
+}
+
+impl Snapshot {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn match_rate(&self) -> Option<f64> {
+        if self.members_seen == 0 {
+            None
+        } else {
+            Some(self.members_matched as f64 / self.members_seen as f64)
+        }
+    }
+
+    pub fn average_mute_wave(&self) -> Option<Duration> {
+        if self.mute_waves == 0 {
+            None
+        } else {
+            Some(Duration::from_millis(
+                self.mute_wave_millis / self.mute_waves,
+            ))
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Telemetry {
+    snapshot: Mutex<Snapshot>,
+    // Time from the watcher reporting a change to the bot finishing the Discord updates for it
+    latencies: Mutex<VecDeque<Duration>>,
+}
+
+impl Telemetry {
+    pub fn record_matching(&self, seen: usize, matched: usize) {
+        let mut snapshot = self.snapshot.lock();
+        snapshot.games += 1;
+        snapshot.members_seen += seen as u64;
+        snapshot.members_matched += matched as u64;
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn record_mute_wave(&self, elapsed: Duration) {
+        let mut snapshot = self.snapshot.lock();
+        snapshot.mute_waves += 1;
+        snapshot.mute_wave_millis += elapsed.as_millis() as u64;
+    }
+
+    pub fn record_source_failure(&self) {
+        self.snapshot.lock().source_failures += 1;
+    }
+
+    pub fn record_transition(&self, latency: Duration) {
+        self.snapshot.lock().transitions += 1;
+
+        let mut latencies = self.latencies.lock();
+        if latencies.len() == LATENCY_WINDOW {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn snapshot(&self) -> Snapshot {
+        let mut latencies = self.latencies.lock().iter().copied().collect::<Vec<_>>();
+        latencies.sort_unstable();
+
+        let percentile = |percent: usize| {
+            if latencies.is_empty() {
+                None
+            } else {
+                let index = (latencies.len() * percent / 100).min(latencies.len() - 1);
+                Some(latencies[index].as_millis() as u64)
+            }
+        };
+
+        Snapshot {
+            latency_p50_millis: percentile(50),
+            latency_p95_millis: percentile(95),
+            ..*self.snapshot.lock()
+        }
+    }
+}
+
+#[cfg(feature = "telemetry")]
+#[derive(Serialize)]
+struct Report {
+    version: &'static str,
+    #[serde(flatten)]
+    snapshot: Snapshot,
+}
+
+// Only started when telemetry_endpoint is set in the config
+#[cfg(feature = "telemetry")]
+pub async fn report(telemetry: Arc<Telemetry>, endpoint: String) -> Result<()> {
+    tracing::info!("Reporting anonymous statistics to {}", endpoint);
+
+    let client = reqwest::Client::new();
+
+    loop {
+        tokio::time::sleep(REPORT_INTERVAL).await;
+
+        client
+            .post(&endpoint)
+            .json(&Report {
+                version: env!("CARGO_PKG_VERSION"),
+                snapshot: telemetry.snapshot(),
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+    }
+}
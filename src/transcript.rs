@@ -0,0 +1,155 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::Write as _,
+    path::Path,
+    time::{Instant, SystemTime},
+};
+
+use taskinator_communicator::game::Player;
+use twilight_cache_inmemory::model::CachedMember;
+use twilight_model::id::UserId;
+
+use crate::{
+    summary::{GameResult, PlayerResult, Team},
+    Result,
+};
+
+const HEADER: &str = "name,discord_id,role,survived,meetings_present,game_duration_secs";
+
+struct PlayerRecord {
+    discord_id: Option<UserId>,
+    impostor: bool,
+    dead: bool,
+    meetings_present: u32,
+}
+
+pub struct Transcript {
+    started: Instant,
+    meetings: u32,
+    players: HashMap<String, PlayerRecord>,
+}
+
+impl Transcript {
+    pub fn new(players: &[Player]) -> Self {
+        let mut transcript = Self {
+            started: Instant::now(),
+            meetings: 0,
+            players: HashMap::new(),
+        };
+        transcript.record_players(players);
+        transcript
+    }
+
+    pub fn record_players(&mut self, players: &[Player]) {
+        for player in players {
+            let record = self
+                .players
+                .entry(player.name.clone())
+                .or_insert(PlayerRecord {
+                    discord_id: None,
+                    impostor: player.impostor,
+                    dead: player.dead,
+                    meetings_present: 0,
+                });
+            record.impostor = player.impostor;
+            record.dead = player.dead;
+        }
+    }
+
+    pub fn record_matches(&mut self, matches: &[(CachedMember, Option<Player>)]) {
+        for (member, player) in matches {
+            if let Some(record) = player
+                .as_ref()
+                .and_then(|p| self.players.get_mut(&p.name))
+            {
+                record.discord_id = Some(member.user_id);
+            }
+        }
+    }
+
+    pub fn record_meeting(&mut self, players: &[Player]) {
+        self.record_players(players);
+        self.meetings += 1;
+
+        for record in self.players.values_mut().filter(|r| !r.dead) {
+            record.meetings_present += 1;
+        }
+    }
+
+    pub fn result(&self) -> GameResult {
+        let alive = |impostor| {
+            self.players
+                .values()
+                .filter(|r| r.impostor == impostor && !r.dead)
+                .count()
+        };
+        let (impostors, crew) = (alive(true), alive(false));
+
+        GameResult {
+            ended: SystemTime::now(),
+            duration: self.started.elapsed(),
+            meetings: self.meetings,
+            winner: if impostors == 0 {
+                Some(Team::Crew)
+            } else if impostors >= crew {
+                Some(Team::Impostors)
+            } else {
+                None
+            },
+            players: self
+                .players
+                .iter()
+                .map(|(name, record)| PlayerResult {
+                    name: name.clone(),
+                    impostor: record.impostor,
+                    survived: !record.dead,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_csv(&self, with_header: bool) -> String {
+        let duration = self.started.elapsed().as_secs();
+        let mut csv = String::new();
+
+        if with_header {
+            csv.push_str(HEADER);
+            csv.push('\n');
+        }
+
+        for (name, record) in &self.players {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                escape(name),
+                record.discord_id.map_or_else(String::new, |id| id.0.to_string()),
+                if record.impostor { "impostor" } else { "crewmate" },
+                !record.dead,
+                record.meetings_present,
+                duration,
+            );
+        }
+
+        csv
+    }
+
+    pub fn append_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let with_header = !path.exists();
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(self.to_csv(with_header).as_bytes())?;
+
+        Ok(())
+    }
+}
+
+fn escape(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
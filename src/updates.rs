@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::time::sleep;
+
+use crate::{bot::Bot, Result};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const LATEST_RELEASE: &str = "https://api.github.com/repos/sam-kirby/taskinator/releases/latest";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+}
+
+// Versions are compared numerically, so 0.10.0 is newer than 0.9.0
+fn version(tag: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = tag.trim_start_matches('v').splitn(3, '.');
+    let mut next = || {
+        parts
+            .next()?
+            .split(|c: char| !c.is_ascii_digit())
+            .next()?
+            .parse()
+            .ok()
+    };
+
+    Some((next()?, next()?, next()?))
+}
+
+async fn latest_release(client: &reqwest::Client) -> Result<Release> {
+    Ok(client
+        .get(LATEST_RELEASE)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+// Only started when check_for_updates is set in the config. Game patches often need a new build,
+// so owners are told about each new release once
+pub async fn check(bot: Bot) -> Result<()> {
+    let current = version(env!("CARGO_PKG_VERSION")).expect("the package version is x.y.z");
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("taskinator/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let mut notified = None;
+
+    loop {
+        // A failed check isn't worth restarting the task over, the next one will do
+        let release = match latest_release(&client).await {
+            Ok(release) => release,
+            Err(why) => {
+                tracing::warn!("Failed to check for updates: {}", why);
+                sleep(CHECK_INTERVAL).await;
+                continue;
+            }
+        };
+
+        match version(&release.tag_name) {
+            Some(latest) if latest > current && notified != Some(latest) => {
+                tracing::info!("Taskinator {} is available", release.tag_name);
+                bot.notify(&format!(
+                    "Taskinator {} is available, this is {}: {}",
+                    release.tag_name,
+                    env!("CARGO_PKG_VERSION"),
+                    release.html_url
+                ))
+                .await?;
+                notified = Some(latest);
+            }
+            Some(_) => {}
+            None => tracing::warn!("Couldn't read the release version {}", release.tag_name),
+        }
+
+        sleep(CHECK_INTERVAL).await;
+    }
+}
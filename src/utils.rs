@@ -1,6 +1,14 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    convert::TryFrom,
+    hash::Hash,
+    time::Duration,
+};
+
 use twilight_cache_inmemory::model::CachedMember;
 use twilight_http::{request::prelude::CreateMessage, Client};
 use twilight_model::{channel::Message, user::User};
+use unicode_normalization::UnicodeNormalization;
 
 use crate::Result;
 
@@ -37,3 +45,231 @@ impl ReplyTo for Message {
             .content(content)?)
     }
 }
+
+// Strips the zero-width and control characters that make in game names impossible to type
+pub fn visible_name(name: &str) -> String {
+    name.chars()
+        .filter(|&c| {
+            !c.is_control() && !matches!(c, '\u{200b}'..='\u{200f}' | '\u{2060}' | '\u{feff}')
+        })
+        .collect::<String>()
+        .trim()
+        .to_lowercase()
+}
+
+// Also folds full-width and other compatibility characters and drops emoji, for servers whose
+// members write their names differently on Discord than in game
+pub fn normalized_name(name: &str) -> String {
+    visible_name(
+        &name
+            .nfkc()
+            .filter(|&c| {
+                !matches!(
+                    c,
+                    '\u{fe0e}' | '\u{fe0f}' | '\u{2600}'..='\u{27bf}' | '\u{1f000}'..='\u{1faff}'
+                )
+            })
+            .collect::<String>(),
+    )
+}
+
+// Levenshtein distance
+pub fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a == *b {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+// Short codes for players by their position in the game, A1 to A9 then B1 and so on, so they can
+// be referred to without typing their names. Past Z9 there are no letters left, and the code can't
+// be claimed
+pub fn short_code(index: usize) -> String {
+    let letter = u8::try_from(index / 9)
+        .ok()
+        .and_then(|letter| b'A'.checked_add(letter))
+        .filter(u8::is_ascii_uppercase)
+        .map_or('?', char::from);
+    format!("{}{}", letter, index % 9 + 1)
+}
+
+pub fn code_index(code: &str) -> Option<usize> {
+    let mut chars = code.trim().chars();
+    // Base 36 digits past 9 are the letters, in either case
+    let letter = chars.next()?.to_digit(36)?.checked_sub(10)?;
+    let digit = chars.next()?.to_digit(10)?.checked_sub(1)?;
+    if chars.next().is_some() {
+        return None;
+    }
+
+    usize::try_from(letter * 9 + digit).ok()
+}
+
+// A whole number of minutes, rejecting any too many to count in seconds
+pub fn parse_minutes(minutes: &str) -> Option<Duration> {
+    let minutes = minutes.trim().parse::<u64>().ok()?;
+    minutes.checked_mul(60).map(Duration::from_secs)
+}
+
+// Durations as people write them, e.g. "8s", "3m" or "1h30m". A bare number is seconds
+pub fn parse_duration(duration: &str) -> Option<Duration> {
+    let duration = duration.trim();
+    if let Ok(secs) = duration.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let mut total = 0_u64;
+    let mut number = String::new();
+    for c in duration.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return None,
+        };
+        total = total.checked_add(number.parse::<u64>().ok()?.checked_mul(unit)?)?;
+        number.clear();
+    }
+
+    // A number without a unit at the end, e.g. "1m30", is ambiguous
+    if duration.is_empty() || !number.is_empty() {
+        return None;
+    }
+
+    Some(Duration::from_secs(total))
+}
+
+// The most recent values seen, oldest forgotten first
+pub struct Recent<T> {
+    order: VecDeque<T>,
+    seen: HashSet<T>,
+    capacity: usize,
+}
+
+impl<T: Copy + Eq + Hash> Recent<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            order: VecDeque::with_capacity(capacity),
+            seen: HashSet::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    // Returns false if the value was already seen
+    pub fn insert(&mut self, value: T) -> bool {
+        if !self.seen.insert(value) {
+            return false;
+        }
+
+        self.order.push_back(value);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_codes_count_up_in_nines() {
+        assert_eq!(short_code(0), "A1");
+        assert_eq!(short_code(8), "A9");
+        assert_eq!(short_code(9), "B1");
+        assert_eq!(short_code(26 * 9), "?1");
+    }
+
+    #[test]
+    fn short_codes_are_read_back_in_either_case() {
+        for index in 0..26 * 9 {
+            assert_eq!(code_index(&short_code(index)), Some(index));
+        }
+        assert_eq!(code_index(" b1 "), Some(9));
+    }
+
+    #[test]
+    fn malformed_codes_are_rejected() {
+        for code in &["", "A", "A0", "A10", "11", "1A", "?1", "AB"] {
+            assert_eq!(code_index(code), None, "{:?}", code);
+        }
+    }
+
+    #[test]
+    fn durations_add_up_their_units() {
+        assert_eq!(parse_duration("8"), Some(Duration::from_secs(8)));
+        assert_eq!(parse_duration("8s"), Some(Duration::from_secs(8)));
+        assert_eq!(parse_duration("3m"), Some(Duration::from_secs(180)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration(" 2d "), Some(Duration::from_secs(172_800)));
+    }
+
+    #[test]
+    fn malformed_durations_are_rejected() {
+        for duration in &["", "m", "1m30", "5x", "-5s", "1.5m"] {
+            assert_eq!(parse_duration(duration), None, "{:?}", duration);
+        }
+    }
+
+    #[test]
+    fn overflowing_durations_are_rejected() {
+        assert_eq!(parse_duration("18446744073709551615d"), None);
+        assert_eq!(parse_duration("18446744073709551615s1s"), None);
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+    }
+
+    #[test]
+    fn minutes_are_counted_in_seconds() {
+        assert_eq!(parse_minutes("10"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_minutes(" 0 "), Some(Duration::from_secs(0)));
+        assert_eq!(parse_minutes("1m"), None);
+        assert_eq!(parse_minutes("-1"), None);
+        assert_eq!(parse_minutes("18446744073709551615"), None);
+    }
+
+    #[test]
+    fn recent_values_are_only_new_once() {
+        let mut recent = Recent::new(2);
+
+        assert!(recent.insert(1));
+        assert!(!recent.insert(1));
+        assert!(recent.insert(2));
+        assert!(!recent.insert(1));
+    }
+
+    #[test]
+    fn the_oldest_recent_value_is_forgotten_first() {
+        let mut recent = Recent::new(2);
+        recent.insert(1);
+        recent.insert(2);
+        recent.insert(3);
+
+        assert!(!recent.insert(2));
+        assert!(!recent.insert(3));
+        assert!(recent.insert(1));
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use reqwest::header::AUTHORIZATION;
+use serde_json::json;
+use tokio::{sync::broadcast::error::RecvError, time::sleep};
+use twilight_model::id::ChannelId;
+
+use crate::{bot::Bot, session::Phase, Result};
+
+// Changes in between are coalesced, so a quick meeting doesn't cost a burst of requests
+const COOLDOWN: Duration = Duration::from_secs(10);
+
+fn status(phase: Phase, paused: bool) -> &'static str {
+    match (phase, paused) {
+        (_, true) | (Phase::PreGame | Phase::GameOver, _) => "",
+        (Phase::InGame, _) => "🔴 Round in progress, stay muted!",
+        (Phase::InMeeting, _) => "🗳️ Meeting",
+    }
+}
+
+// twilight doesn't know the voice channel status endpoint, so it's called directly. An empty
+// status clears it
+async fn set(
+    client: &reqwest::Client,
+    token: &str,
+    channel_id: ChannelId,
+    status: &str,
+) -> Result<()> {
+    client
+        .put(format!(
+            "https://discord.com/api/v10/channels/{}/voice-status",
+            channel_id
+        ))
+        .header(AUTHORIZATION, format!("Bot {}", token))
+        .json(&json!({ "status": status }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+// Only started when voice_status is set in the config. Shows the game phase under the living
+// channel's name, and clears it between games
+pub async fn show(bot: Bot, token: String) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("taskinator/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let mut session_events = bot.subscribe();
+    let mut shown: Option<(ChannelId, &str)> = None;
+
+    loop {
+        let channel_id = bot.living_channel();
+        let status = status(bot.game_phase(), bot.is_paused());
+
+        if shown != Some((channel_id, status)) {
+            // A profile switch moves the game to another channel, which shouldn't keep the status
+            if let Some((previous, _)) = shown.filter(|(previous, _)| *previous != channel_id) {
+                set(&client, &token, previous, "").await?;
+            }
+            set(&client, &token, channel_id, status).await?;
+            shown = Some((channel_id, status));
+            sleep(COOLDOWN).await;
+            continue;
+        }
+
+        match session_events.recv().await {
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
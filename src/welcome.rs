@@ -0,0 +1,29 @@
+use twilight_http::Client;
+use twilight_model::guild::Guild;
+
+use crate::{store::Store, Result};
+
+const WELCOME: &str = "Thanks for adding Taskinator! Commands are disabled in this server until \
+    it has been set up. Follow the configuration steps at https://github.com/sam-kirby/taskinator, \
+    then have a bot owner run `~setup` here to enable it.";
+
+// Servers the bot hasn't seen before get a disabled entry in the store, so commands there can't
+// control the game until an owner enables them
+pub async fn onboard(client: &Client, store: &Store, guild: &Guild) -> Result<()> {
+    if store.contains(guild.id) {
+        return Ok(());
+    }
+
+    tracing::info!("Joined new guild {} ({})", guild.name, guild.id);
+
+    store.update_guild(guild.id, |settings| settings.disabled = true)?;
+
+    let channel = match guild.system_channel_id {
+        Some(channel) => channel,
+        None => client.create_private_channel(guild.owner_id).await?.id,
+    };
+
+    client.create_message(channel).content(WELCOME)?.await?;
+
+    Ok(())
+}